@@ -1,8 +1,11 @@
-use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+use circuitbreaker_rs::{BreakerError, CircuitBreaker, DefaultPolicy, FixedWindow};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use parking_lot::Mutex;
 use std::error::Error;
 use std::fmt;
-use std::time::Duration;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
 
 // Custom error type that implements Error trait
 #[derive(Debug)]
@@ -71,9 +74,6 @@ fn bench_circuit_breaker_transition(c: &mut Criterion) {
 }
 
 fn bench_circuit_breaker_concurrent(c: &mut Criterion) {
-    use std::sync::{Arc, Barrier};
-    use std::thread;
-
     let breaker = Arc::new(
         CircuitBreaker::<DefaultPolicy, BenchError>::builder()
             .failure_threshold(0.5)
@@ -113,10 +113,254 @@ fn bench_circuit_breaker_concurrent(c: &mut Criterion) {
     });
 }
 
+// The mutex-guarded timestamps the breaker updated on every call before they
+// became atomic offsets, kept as the baseline for the contention benchmark
+struct MutexTimestamps {
+    last_failure_time: Mutex<Option<Instant>>,
+    last_success_time: Mutex<Option<Instant>>,
+    last_transition: Mutex<Instant>,
+}
+
+impl MutexTimestamps {
+    fn new() -> Self {
+        Self {
+            last_failure_time: Mutex::new(None),
+            last_success_time: Mutex::new(None),
+            last_transition: Mutex::new(Instant::now()),
+        }
+    }
+
+    // Takes the locks the breaker used to take for a call with this result
+    fn record(&self, result: &Result<(), BreakerError<BenchError>>) {
+        match result {
+            Ok(()) => *self.last_success_time.lock() = Some(Instant::now()),
+            Err(BreakerError::Operation(_)) => {
+                *self.last_failure_time.lock() = Some(Instant::now())
+            }
+            Err(_) => {
+                black_box(self.last_transition.lock().elapsed());
+            }
+        }
+    }
+}
+
+// Calls `breaker` `ITERATIONS_PER_THREAD` times on each of `threads` threads at once,
+// failing every other call if `fail` is set. With `baseline`, every call also
+// takes the locks of the mutex-guarded timestamps.
+fn call_from_threads(
+    breaker: &Arc<CircuitBreaker<DefaultPolicy, BenchError>>,
+    threads: usize,
+    fail: bool,
+    baseline: Option<&Arc<MutexTimestamps>>,
+) {
+    const ITERATIONS_PER_THREAD: usize = 10_000;
+
+    let barrier = Arc::new(Barrier::new(threads + 1));
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let breaker = Arc::clone(breaker);
+            let baseline = baseline.cloned();
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                for i in 0..ITERATIONS_PER_THREAD {
+                    let result = if fail && i % 2 == 0 {
+                        breaker.call(failing_operation)
+                    } else {
+                        breaker.call(successful_operation)
+                    };
+                    if let Some(baseline) = &baseline {
+                        baseline.record(&result);
+                    }
+                    let _ = black_box(result);
+                }
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+fn bench_circuit_breaker_contended(c: &mut Criterion) {
+    const THREAD_COUNT: usize = 8;
+
+    let mut group = c.benchmark_group("circuit_breaker_contended");
+    let timestamps = Arc::new(MutexTimestamps::new());
+
+    // Every call records its outcome and timestamp
+    let closed = Arc::new(
+        CircuitBreaker::<DefaultPolicy, BenchError>::builder()
+            .failure_threshold(1.0)
+            .consecutive_failures(u64::MAX)
+            .build(),
+    );
+    group.bench_function("closed_mixed", |b| {
+        b.iter(|| call_from_threads(&closed, THREAD_COUNT, true, None))
+    });
+    group.bench_function("closed_mixed_mutex", |b| {
+        b.iter(|| call_from_threads(&closed, THREAD_COUNT, true, Some(&timestamps)))
+    });
+
+    // Every call checks the time spent in the open state
+    let open = Arc::new(
+        CircuitBreaker::<DefaultPolicy, BenchError>::builder()
+            .cooldown(Duration::from_secs(3600))
+            .build(),
+    );
+    open.force_open();
+    group.bench_function("open_rejected", |b| {
+        b.iter(|| call_from_threads(&open, THREAD_COUNT, false, None))
+    });
+    group.bench_function("open_rejected_mutex", |b| {
+        b.iter(|| call_from_threads(&open, THREAD_COUNT, false, Some(&timestamps)))
+    });
+
+    group.finish();
+}
+
+fn bench_counter_shards(c: &mut Criterion) {
+    let threads = thread::available_parallelism()
+        .map_or(8, |n| n.get())
+        .max(8);
+    let mut group = c.benchmark_group("circuit_breaker_counter_shards");
+
+    for shards in [1, threads] {
+        let breaker = Arc::new(
+            CircuitBreaker::<DefaultPolicy, BenchError>::builder()
+                .counter_shards(shards)
+                .build(),
+        );
+        group.bench_function(format!("{}_threads_{}_shards", threads, shards), |b| {
+            b.iter(|| call_from_threads(&breaker, threads, false, None))
+        });
+    }
+
+    group.finish();
+}
+
+// The mutex-guarded window `FixedWindow` used before it became a lock-free ring,
+// kept as the baseline for the contention benchmark
+struct MutexWindow {
+    buckets: Mutex<Vec<(Instant, u64, u64)>>, // (timestamp, successes, failures)
+    window_size: Duration,
+    bucket_size: Duration,
+}
+
+impl MutexWindow {
+    fn new(window_size: Duration, bucket_count: u32) -> Self {
+        Self {
+            buckets: Mutex::new(Vec::new()),
+            window_size,
+            bucket_size: window_size / bucket_count,
+        }
+    }
+
+    fn record(&self, failure: bool) {
+        let mut buckets = self.buckets.lock();
+        let now = Instant::now();
+        let cutoff = now - self.window_size;
+        while buckets.first().is_some_and(|bucket| bucket.0 < cutoff) {
+            buckets.remove(0);
+        }
+
+        if let Some(bucket) = buckets.last_mut() {
+            if now.duration_since(bucket.0) < self.bucket_size {
+                if failure {
+                    bucket.2 += 1;
+                } else {
+                    bucket.1 += 1;
+                }
+                return;
+            }
+        }
+
+        buckets.push((now, !failure as u64, failure as u64));
+    }
+
+    fn error_rate(&self) -> f64 {
+        let buckets = self.buckets.lock();
+        let (successes, failures) = buckets
+            .iter()
+            .fold((0, 0), |(s, f), bucket| (s + bucket.1, f + bucket.2));
+        if successes + failures == 0 {
+            return 0.0;
+        }
+        failures as f64 / (successes + failures) as f64
+    }
+}
+
+// Runs `ITERATIONS_PER_THREAD` operations on each of `THREAD_COUNT` threads, with
+// every eighth operation reading the error rate instead of recording
+fn contend<W: Send + Sync + 'static>(
+    window: &Arc<W>,
+    record: fn(&W, bool),
+    error_rate: fn(&W) -> f64,
+) {
+    const THREAD_COUNT: usize = 8;
+    const ITERATIONS_PER_THREAD: usize = 10_000;
+
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT + 1));
+    let handles: Vec<_> = (0..THREAD_COUNT)
+        .map(|_| {
+            let window = Arc::clone(window);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                for i in 0..ITERATIONS_PER_THREAD {
+                    if i % 8 == 0 {
+                        black_box(error_rate(&window));
+                    } else {
+                        record(&window, i % 3 == 0);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+fn bench_fixed_window_contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fixed_window_contention");
+
+    let mutex_window = Arc::new(MutexWindow::new(Duration::from_secs(10), 10));
+    group.bench_function("mutex", |b| {
+        b.iter(|| contend(&mutex_window, MutexWindow::record, MutexWindow::error_rate));
+    });
+
+    let ring_window = Arc::new(FixedWindow::new(Duration::from_secs(10), 10));
+    group.bench_function("lock_free", |b| {
+        b.iter(|| {
+            contend(
+                &ring_window,
+                |window, failure| {
+                    if failure {
+                        window.record_failure()
+                    } else {
+                        window.record_success()
+                    }
+                },
+                FixedWindow::error_rate,
+            )
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_circuit_breaker_closed,
     bench_circuit_breaker_transition,
-    bench_circuit_breaker_concurrent
+    bench_circuit_breaker_concurrent,
+    bench_circuit_breaker_contended,
+    bench_counter_shards,
+    bench_fixed_window_contention
 );
 criterion_main!(benches);
@@ -33,7 +33,7 @@ fn main() {
     let call_service = |counter: &mut u32| -> Result<String, ServiceError> {
         if *counter < 10 {
             *counter += 1;
-            if *counter % 2 == 0 {
+            if counter.is_multiple_of(2) {
                 // Simulate an error on even counts
                 Err(ServiceError("External service error".to_string()))
             } else {
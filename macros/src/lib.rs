@@ -0,0 +1,233 @@
+//! # circuitbreaker-rs-macros
+//!
+//! The `#[circuit_breaker]` attribute of
+//! [circuitbreaker-rs](https://github.com/copyleftdev/circuitbreaker-rs).
+//!
+//! Use it through the `macros` feature of `circuitbreaker-rs`, which re-exports
+//! it as `circuitbreaker_rs::circuit_breaker`.
+
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, FnArg, GenericArgument, Ident, ItemFn, LitStr, Pat, Path, PathArguments,
+    ReturnType, Type,
+};
+
+/// Runs a function through a circuit breaker of the global registry.
+///
+/// The function must return `Result<T, E>`. It is rewritten to return
+/// `BreakerResult<T, E>`, with the errors of its body as
+/// `BreakerError::Operation`. The breaker is looked up by name in
+/// `BreakerRegistry::global()`, and created with the default settings on the
+/// first call if no breaker was registered under that name.
+///
+/// - `name`: the name of the breaker. Defaults to the module path and name of
+///   the function, e.g. `my_crate::payments::fetch_payment`, so that functions
+///   of the same name in different modules get their own breakers.
+/// - `fallback`: a function called with the same arguments when the circuit is
+///   open, returning `Result<T, E>` too. It must be async if the function is.
+///   For methods, it names another method of the same type.
+///
+/// Arguments must be plain identifiers, so that they can be forwarded to the
+/// fallback. Async functions need the `async` feature of `circuitbreaker-rs`.
+///
+/// ```rust,ignore
+/// use circuitbreaker_rs::circuit_breaker;
+///
+/// #[circuit_breaker(name = "payments", fallback = "cached_payment")]
+/// async fn fetch_payment(id: u64) -> Result<Payment, PaymentError> {
+///     payments_api::get(id).await
+/// }
+///
+/// async fn cached_payment(id: u64) -> Result<Payment, PaymentError> {
+///     cache::get(id).ok_or(PaymentError::NotFound)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn circuit_breaker(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut name: Option<LitStr> = None;
+    let mut fallback: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("fallback") {
+            fallback = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported circuit_breaker property, expected `name` or `fallback`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand(name, fallback, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(
+    name: Option<LitStr>,
+    fallback: Option<LitStr>,
+    mut item: ItemFn,
+) -> syn::Result<TokenStream2> {
+    let name = match name {
+        Some(name) => quote!(#name),
+        None => {
+            let ident = &item.sig.ident;
+            quote!(::core::concat!(
+                ::core::module_path!(),
+                "::",
+                ::core::stringify!(#ident)
+            ))
+        }
+    };
+    let (ok, err) = result_types(&item.sig.output)?;
+    let (ok, err) = (ok.clone(), err.clone());
+    let ReturnType::Type(_, output) = item.sig.output.clone() else {
+        unreachable!("checked by result_types");
+    };
+    let is_async = item.sig.asyncness.is_some();
+
+    // Arguments are moved into the breaker's closure, or to the fallback
+    let mut has_self = false;
+    let mut patterns = Vec::new();
+    let mut idents = Vec::new();
+    for input in &mut item.sig.inputs {
+        match input {
+            FnArg::Receiver(_) => has_self = true,
+            FnArg::Typed(arg) => match &mut *arg.pat {
+                Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                    patterns.push(pat.clone());
+                    idents.push(pat.ident.clone());
+                    // Only the binding inside the closure is mutated
+                    pat.mutability = None;
+                }
+                pat => {
+                    return Err(syn::Error::new(
+                        pat.span(),
+                        "circuit_breaker arguments must be identifiers",
+                    ))
+                }
+            },
+        }
+    }
+
+    let krate = quote!(::circuitbreaker_rs);
+    item.sig.output = syn::parse_quote!(-> #krate::BreakerResult<#ok, #err>);
+    let block = &item.block;
+    let take_args = quote! {
+        __circuit_breaker_args
+            .take()
+            .expect("arguments are only taken once")
+    };
+
+    let call = if is_async {
+        quote! {
+            __circuit_breaker
+                .call_async(|| {
+                    let (#(#patterns,)*) = #take_args;
+                    async move {
+                        let __output: #output = #block;
+                        __output
+                    }
+                })
+                .await
+        }
+    } else {
+        quote! {
+            __circuit_breaker.call(|| -> #output {
+                let (#(#patterns,)*) = #take_args;
+                #block
+            })
+        }
+    };
+    let result = match fallback {
+        Some(fallback) => {
+            let fallback_call = fallback_call(&fallback, has_self, &idents)?;
+            let fallback_call = if is_async {
+                quote!(#fallback_call.await)
+            } else {
+                fallback_call
+            };
+            quote! {
+                match __circuit_breaker_result {
+                    ::core::result::Result::Err(#krate::BreakerError::Open) => {
+                        let (#(#idents,)*) = #take_args;
+                        #fallback_call.map_err(#krate::BreakerError::Operation)
+                    }
+                    __circuit_breaker_result => __circuit_breaker_result,
+                }
+            }
+        }
+        None => quote!(__circuit_breaker_result),
+    };
+
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let sig = &item.sig;
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            let __circuit_breaker = #krate::BreakerRegistry::global()
+                .get_or_insert_with::<#err, _>(#name, || {
+                    #krate::CircuitBreaker::<#krate::DefaultPolicy, #err>::builder()
+                        .boxed()
+                        .build_with_policy()
+                });
+            let mut __circuit_breaker_args = ::core::option::Option::Some((#(#idents,)*));
+            let __circuit_breaker_result = #call;
+            #result
+        }
+    })
+}
+
+/// Gets `T` and `E` from a return type written as `Result<T, E>`.
+fn result_types(output: &ReturnType) -> syn::Result<(&Type, &Type)> {
+    let error = || {
+        syn::Error::new(
+            output.span(),
+            "circuit_breaker functions must return `Result<T, E>`",
+        )
+    };
+
+    let ReturnType::Type(_, ty) = output else {
+        return Err(error());
+    };
+    let Type::Path(path) = &**ty else {
+        return Err(error());
+    };
+    let segment = path.path.segments.last().ok_or_else(error)?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Err(error());
+    };
+    let mut types = args.args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    match (
+        segment.ident == "Result",
+        types.next(),
+        types.next(),
+        types.next(),
+    ) {
+        (true, Some(ok), Some(err), None) => Ok((ok, err)),
+        _ => Err(error()),
+    }
+}
+
+/// Builds the call to the fallback, forwarding the arguments.
+fn fallback_call(fallback: &LitStr, has_self: bool, idents: &[Ident]) -> syn::Result<TokenStream2> {
+    if has_self {
+        let method: Ident = fallback.parse()?;
+        Ok(quote!(self.#method(#(#idents),*)))
+    } else {
+        let path: Path = fallback.parse()?;
+        Ok(quote!(#path(#(#idents),*)))
+    }
+}
@@ -1,31 +1,50 @@
 //! Core circuit breaker implementation.
 
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::error::{BreakerError, BreakerResult};
+use crate::bulkhead::Bulkhead;
+use crate::config::RuntimeConfig;
+use crate::error::{BreakerError, BreakerResult, ConfigError};
+#[cfg(feature = "async")]
+use crate::hedge::Hedge;
 use crate::hook::HookRegistry;
-use crate::metrics::{BreakerStats, MetricSink};
+use crate::limiter::AdaptiveLimiter;
+use crate::metrics::{BreakerStats, MetricSink, StatsView};
 use crate::policy::BreakerPolicy;
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryBudget;
 use crate::state::{State, StateManager};
+use crate::time::AtomicInstant;
 
 /// Inner state of the circuit breaker, shared between instances.
-struct BreakerInner<P>
+pub(crate) struct BreakerInner<P>
 where
     P: BreakerPolicy,
 {
     state_manager: StateManager,
     policy: P,
     stats: BreakerStats,
-    cooldown_duration: Duration,
+    cooldown_nanos: AtomicU64,
     probes_allowed: AtomicU32,
-    probe_interval: u32,
-    last_probe_time: parking_lot::Mutex<Instant>,
+    probe_interval: AtomicU32,
+    config_lock: parking_lot::Mutex<()>,
+    last_probe_time: AtomicInstant,
+    limits: CallLimits,
     metric_sink: Arc<dyn MetricSink>,
     hooks: Arc<HookRegistry>,
 }
 
+/// Optional limits on the calls made through a breaker.
+#[derive(Default)]
+pub(crate) struct CallLimits {
+    pub(crate) bulkhead: Option<Bulkhead>,
+    pub(crate) limiter: Option<AdaptiveLimiter>,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) retry_budget: Option<Arc<RetryBudget>>,
+}
+
 /// A circuit breaker that can wrap function calls to prevent cascading failures.
 pub struct CircuitBreaker<P, E>
 where
@@ -33,7 +52,8 @@ where
     E: std::error::Error + 'static,
 {
     inner: Arc<BreakerInner<P>>,
-    _error_type: std::marker::PhantomData<E>,
+    // The breaker never holds an `E`, so it is `Send` and `Sync` whatever `E` is
+    _error_type: std::marker::PhantomData<fn() -> E>,
 }
 
 impl<P, E> CircuitBreaker<P, E>
@@ -48,15 +68,39 @@ where
         probe_interval: u32,
         metric_sink: Arc<dyn MetricSink>,
         hooks: Arc<HookRegistry>,
+    ) -> Self {
+        Self::with_stats(
+            policy,
+            cooldown_duration,
+            probe_interval,
+            metric_sink,
+            hooks,
+            BreakerStats::new(),
+            CallLimits::default(),
+        )
+    }
+
+    /// Creates a new circuit breaker that records into the given statistics and
+    /// enforces the given limits on concurrent calls.
+    pub(crate) fn with_stats(
+        policy: P,
+        cooldown_duration: Duration,
+        probe_interval: u32,
+        metric_sink: Arc<dyn MetricSink>,
+        hooks: Arc<HookRegistry>,
+        stats: BreakerStats,
+        limits: CallLimits,
     ) -> Self {
         let inner = BreakerInner {
             state_manager: StateManager::new(),
             policy,
-            stats: BreakerStats::new(),
-            cooldown_duration,
+            stats,
+            cooldown_nanos: AtomicU64::new(duration_to_nanos(cooldown_duration)),
             probes_allowed: AtomicU32::new(0),
-            probe_interval,
-            last_probe_time: parking_lot::Mutex::new(Instant::now()),
+            probe_interval: AtomicU32::new(probe_interval),
+            config_lock: parking_lot::Mutex::new(()),
+            last_probe_time: AtomicInstant::now(),
+            limits,
             metric_sink,
             hooks,
         };
@@ -67,6 +111,19 @@ where
         }
     }
 
+    /// Wraps shared breaker state in a handle with the given error type.
+    pub(crate) fn from_inner(inner: Arc<BreakerInner<P>>) -> Self {
+        Self {
+            inner,
+            _error_type: std::marker::PhantomData,
+        }
+    }
+
+    /// Gets the shared breaker state.
+    pub(crate) fn inner(&self) -> &Arc<BreakerInner<P>> {
+        &self.inner
+    }
+
     /// Creates a new builder for customizing a circuit breaker.
     pub fn builder() -> crate::config::BreakerBuilder<crate::policy::DefaultPolicy, E> {
         crate::config::BreakerBuilder::new()
@@ -77,41 +134,215 @@ where
         self.inner.state_manager.current()
     }
 
+    /// Gets the time left before an open circuit lets probes through, or zero
+    /// unless the circuit is open.
+    pub fn remaining_cooldown(&self) -> Duration {
+        if self.inner.state_manager.current() != State::Open {
+            return Duration::ZERO;
+        }
+        self.cooldown()
+            .saturating_sub(self.inner.state_manager.time_in_state())
+    }
+
+    /// Gets the number of calls holding a bulkhead slot, or `None` if no bulkhead
+    /// was configured with [`BreakerBuilder::max_concurrent_calls`].
+    ///
+    /// [`BreakerBuilder::max_concurrent_calls`]: crate::BreakerBuilder::max_concurrent_calls
+    pub fn bulkhead_in_flight(&self) -> Option<usize> {
+        self.inner.limits.bulkhead.as_ref().map(Bulkhead::in_flight)
+    }
+
+    /// Gets the retry budget attached to the breaker, if any.
+    pub fn retry_budget(&self) -> Option<&Arc<RetryBudget>> {
+        self.inner.limits.retry_budget.as_ref()
+    }
+
+    /// Takes a retry from the breaker's retry budget, or returns `true` if there
+    /// is no budget.
+    pub(crate) fn withdraw_retry(&self) -> bool {
+        let Some(budget) = &self.inner.limits.retry_budget else {
+            return true;
+        };
+
+        let withdrawn = budget.try_withdraw();
+        self.inner
+            .metric_sink
+            .record_retry_budget(budget.balance(), withdrawn);
+        withdrawn
+    }
+
+    /// Reports a call rejected before it reached the breaker, such as by the
+    /// bulkhead of a [`Pipeline`](crate::Pipeline).
+    pub(crate) fn record_rejection(&self, reason: &str) {
+        self.inner.metric_sink.record_rejection(reason);
+    }
+
+    /// Gets the breaker's concurrency limiter, if one was configured.
+    pub fn limiter(&self) -> Option<&AdaptiveLimiter> {
+        self.inner.limits.limiter.as_ref()
+    }
+
     /// Gets the current error rate of the circuit breaker.
     pub fn error_rate(&self) -> f64 {
         self.inner.stats.error_rate()
     }
 
+    /// Takes a consistent snapshot of the circuit breaker's statistics.
+    pub fn stats(&self) -> StatsView {
+        self.inner.stats.snapshot()
+    }
+
+    /// Gets the current runtime configuration of the circuit breaker.
+    pub fn config(&self) -> RuntimeConfig {
+        RuntimeConfig {
+            cooldown: self.cooldown(),
+            probe_interval: self.inner.probe_interval.load(Ordering::Relaxed),
+            thresholds: self.inner.policy.thresholds(),
+        }
+    }
+
+    /// Updates the configuration of a running circuit breaker.
+    ///
+    /// The closure receives the current configuration and may modify it in place.
+    /// Concurrent updates are serialized, and the breaker's state and statistics are
+    /// preserved. A changed probe count takes effect the next time the circuit
+    /// half-opens. Threshold changes are ignored if the policy does not support
+    /// runtime reconfiguration.
+    ///
+    /// When the configuration changes, the config change hook and metric sink are
+    /// notified with the old and new values. If the updated configuration is
+    /// invalid, nothing is changed and every invalid setting is returned.
+    pub fn update_config<F>(&self, f: F) -> Result<(), ConfigError>
+    where
+        F: FnOnce(&mut RuntimeConfig),
+    {
+        let (old, new) = {
+            let _guard = self.inner.config_lock.lock();
+
+            let old = self.config();
+            let mut new = old;
+            f(&mut new);
+            new.validate()?;
+
+            self.inner
+                .cooldown_nanos
+                .store(duration_to_nanos(new.cooldown), Ordering::Relaxed);
+            self.inner
+                .probe_interval
+                .store(new.probe_interval, Ordering::Relaxed);
+            if let Some(thresholds) = new.thresholds {
+                self.inner.policy.update_thresholds(thresholds);
+            }
+
+            (old, self.config())
+        };
+
+        if old != new {
+            // Execute hook outside the lock path
+            self.inner.hooks.execute_config_change_hook(&old, &new);
+
+            // Record metric
+            self.inner.metric_sink.record_config_change(&old, &new);
+        }
+
+        Ok(())
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_nanos(self.inner.cooldown_nanos.load(Ordering::Relaxed))
+    }
+
     /// Executes a function wrapped by the circuit breaker.
     pub fn call<F, T>(&self, f: F) -> BreakerResult<T, E>
     where
         F: FnOnce() -> Result<T, E>,
     {
-        self.pre_call()?;
+        self.check_rate_limit()?;
+        self.check_open()?;
+        let bulkhead_slot = self
+            .inner
+            .limits
+            .bulkhead
+            .as_ref()
+            .is_none_or(Bulkhead::acquire);
+        let permit = self.pre_call(bulkhead_slot)?;
 
         let start = Instant::now();
         let result = f();
         let duration = start.elapsed();
 
         self.post_call(&result, duration);
+        permit.complete(result.is_ok(), duration);
 
         result.map_err(BreakerError::Operation)
     }
 
-    /// Checks if a call is allowed based on the current state.
-    fn pre_call(&self) -> Result<(), BreakerError<E>> {
+    /// Takes a token from the rate limiter if there is one.
+    fn check_rate_limit(&self) -> Result<(), BreakerError<E>> {
+        match &self.inner.limits.rate_limiter {
+            Some(rate_limiter) if !rate_limiter.try_acquire() => {
+                self.inner.metric_sink.record_rejection("rate_limited");
+                Err(BreakerError::RateLimited)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects the call up front while the circuit is open and cooling down, so
+    /// that it fails fast instead of queueing for a bulkhead slot first.
+    fn check_open(&self) -> Result<(), BreakerError<E>> {
+        if self.remaining_cooldown().is_zero() {
+            return Ok(());
+        }
+
+        self.inner.metric_sink.record_rejection("open");
+        Err(BreakerError::Open)
+    }
+
+    /// Checks if a call is allowed based on the current state, and takes a slot
+    /// from the concurrency limiter if there is one.
+    ///
+    /// `bulkhead_slot` tells whether the call got a slot in the bulkhead, and is
+    /// `true` if there is no bulkhead.
+    fn pre_call(&self, bulkhead_slot: bool) -> Result<CallPermit<'_>, BreakerError<E>> {
+        if !bulkhead_slot {
+            self.inner.metric_sink.record_rejection("bulkhead_full");
+            return Err(BreakerError::BulkheadFull);
+        }
+        let mut permit = CallPermit {
+            bulkhead: self.inner.limits.bulkhead.as_ref(),
+            limiter: None,
+            probe: false,
+        };
+
+        if let Some(limiter) = &self.inner.limits.limiter {
+            if !limiter.try_acquire() {
+                self.inner.metric_sink.record_rejection("limit_exceeded");
+                return Err(BreakerError::LimitExceeded);
+            }
+            permit.limiter = Some(limiter);
+        }
+
         match self.inner.state_manager.current() {
-            State::Closed => Ok(()),
+            State::Closed => {
+                if self.inner.policy.admit() {
+                    Ok(permit)
+                } else {
+                    self.inner.metric_sink.record_rejection("throttled");
+                    Err(BreakerError::Throttled)
+                }
+            }
             State::Open => {
                 // Check if cooldown period has elapsed
-                if self.inner.state_manager.time_in_state() >= self.inner.cooldown_duration {
+                if self.inner.state_manager.time_in_state() >= self.cooldown() {
                     // Attempt to transition to half-open
                     if self.inner.state_manager.attempt_half_open() {
                         // Reset probe counter
-                        self.inner
-                            .probes_allowed
-                            .store(self.inner.probe_interval, Ordering::Relaxed);
-                        *self.inner.last_probe_time.lock() = Instant::now();
+                        self.inner.probes_allowed.store(
+                            self.inner.probe_interval.load(Ordering::Relaxed),
+                            Ordering::Relaxed,
+                        );
+                        self.inner.last_probe_time.set_now();
 
                         // Execute hook outside the lock path
                         self.inner
@@ -123,10 +354,11 @@ where
                             .metric_sink
                             .record_state_transition("open", "half-open");
 
-                        return Ok(());
+                        return Ok(permit);
                     }
                 }
 
+                self.inner.metric_sink.record_rejection("open");
                 Err(BreakerError::Open)
             }
             State::HalfOpen => {
@@ -139,10 +371,12 @@ where
                     // Record metric
                     self.inner.metric_sink.record_probe_attempt(true);
 
-                    Ok(())
+                    permit.probe = true;
+                    Ok(permit)
                 } else {
                     // Record metric
                     self.inner.metric_sink.record_probe_attempt(false);
+                    self.inner.metric_sink.record_rejection("open");
 
                     Err(BreakerError::Open)
                 }
@@ -159,16 +393,20 @@ where
         self.inner.metric_sink.record_call(success, duration);
 
         if success {
+            if let Some(budget) = &self.inner.limits.retry_budget {
+                budget.deposit();
+            }
             self.inner.stats.record_success();
+            self.inner.policy.record_outcome(true, duration);
             self.inner.hooks.execute_success_hook();
 
             // If in half-open state and should reset to closed
             if current_state == State::HalfOpen
-                && self.inner.policy.should_reset(&self.inner.stats)
+                && self.inner.policy.should_reset(&self.inner.stats.snapshot())
                 && self.inner.state_manager.reset_closed()
             {
                 // Reset stats
-                self.inner.stats.reset();
+                self.reset_stats();
 
                 // Execute hook outside the lock path
                 self.inner
@@ -179,9 +417,12 @@ where
                 self.inner
                     .metric_sink
                     .record_state_transition("half-open", "closed");
+            } else if current_state == State::Closed && self.inner.policy.trips_on_success() {
+                self.evaluate_trip();
             }
         } else {
             self.inner.stats.record_failure();
+            self.inner.policy.record_outcome(false, duration);
             self.inner.hooks.execute_failure_hook();
 
             // If in half-open state, revert to open
@@ -195,26 +436,29 @@ where
                         .metric_sink
                         .record_state_transition("half-open", "open");
                 }
-            } else if current_state == State::Closed
-                && self.inner.policy.should_trip(&self.inner.stats)
-            {
-                // If in closed state and should trip
-                if self.inner.state_manager.trip_open() {
-                    // Execute hook outside the lock path
-                    self.inner.hooks.execute_state_transition_hook(State::Open);
-
-                    // Record metric
-                    self.inner
-                        .metric_sink
-                        .record_state_transition("closed", "open");
-                    self.inner
-                        .metric_sink
-                        .record_error_rate(self.inner.stats.error_rate());
-                }
+            } else if current_state == State::Closed {
+                self.evaluate_trip();
             }
         }
     }
 
+    /// Trips the circuit if the policy decides so on the current stats.
+    fn evaluate_trip(&self) {
+        let stats = self.inner.stats.snapshot();
+
+        // If in closed state and should trip
+        if self.inner.policy.should_trip(&stats) && self.inner.state_manager.trip_open() {
+            // Execute hook outside the lock path
+            self.inner.hooks.execute_state_transition_hook(State::Open);
+
+            // Record metric
+            self.inner
+                .metric_sink
+                .record_state_transition("closed", "open");
+            self.inner.metric_sink.record_error_rate(stats.error_rate());
+        }
+    }
+
     /// Forces the circuit breaker to the open state.
     pub fn force_open(&self) -> bool {
         let current = self.inner.state_manager.current();
@@ -259,7 +503,7 @@ where
 
         if result {
             // Reset stats
-            self.inner.stats.reset();
+            self.reset_stats();
 
             // Execute hook outside the lock path
             self.inner
@@ -280,12 +524,50 @@ where
         result
     }
 
-    /// Resets the circuit breaker's statistics.
+    /// Resets the circuit breaker's statistics, including any call history kept
+    /// by its policy.
     pub fn reset_stats(&self) {
         self.inner.stats.reset();
+        self.inner.policy.clear();
     }
 }
 
+/// A call admitted by the breaker, holding its slots in the bulkhead and the
+/// concurrency limiter.
+///
+/// The slots are released on drop, so that they are also returned when the call
+/// panics or its future is dropped.
+struct CallPermit<'a> {
+    bulkhead: Option<&'a Bulkhead>,
+    limiter: Option<&'a AdaptiveLimiter>,
+    // Whether the call took one of the probes of a half-open circuit
+    probe: bool,
+}
+
+impl CallPermit<'_> {
+    /// Lets the limiter adapt to the outcome of the call.
+    fn complete(self, success: bool, duration: Duration) {
+        if let Some(limiter) = self.limiter {
+            limiter.record(success, duration);
+        }
+    }
+}
+
+impl Drop for CallPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(limiter) = self.limiter {
+            limiter.release();
+        }
+        if let Some(bulkhead) = self.bulkhead {
+            bulkhead.release();
+        }
+    }
+}
+
+fn duration_to_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
 // Allow cloning of circuit breakers - cheap because inner state is Arc'd
 impl<P, E> Clone for CircuitBreaker<P, E>
 where
@@ -313,14 +595,95 @@ where
         F: FnOnce() -> Fut,
         Fut: std::future::Future<Output = Result<T, E>>,
     {
-        self.pre_call()?;
+        self.call_async_ignoring(f, |_| false).await
+    }
+
+    /// Executes an async function wrapped by the circuit breaker, without
+    /// recording the outcomes for which `ignored` returns `true`.
+    ///
+    /// An ignored call that was a half-open probe hands the probe back, so that
+    /// ignored outcomes cannot leave the circuit half-open for good.
+    pub(crate) async fn call_async_ignoring<F, Fut, T, I>(
+        &self,
+        f: F,
+        ignored: I,
+    ) -> BreakerResult<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+        I: FnOnce(&Result<T, E>) -> bool,
+    {
+        self.check_rate_limit()?;
+        self.check_open()?;
+        let bulkhead_slot = match &self.inner.limits.bulkhead {
+            Some(bulkhead) => bulkhead.acquire_async().await,
+            None => true,
+        };
+        let permit = self.pre_call(bulkhead_slot)?;
 
         let start = Instant::now();
         let result = f().await;
         let duration = start.elapsed();
 
-        self.post_call(&result, duration);
+        if !ignored(&result) {
+            self.post_call(&result, duration);
+            permit.complete(result.is_ok(), duration);
+        } else if permit.probe && self.inner.state_manager.current() == State::HalfOpen {
+            self.inner.probes_allowed.fetch_add(1, Ordering::Relaxed);
+        }
 
         result.map_err(BreakerError::Operation)
     }
+
+    /// Executes an async function wrapped by the circuit breaker, hedging calls
+    /// that are slow to complete.
+    ///
+    /// If the first attempt has not completed after [`Hedge::delay`], a second
+    /// attempt is started and the first success of the two is returned. The other
+    /// attempt is then dropped, which cancels it. Each attempt goes through the
+    /// breaker on its own, so every attempt that completes is recorded as a
+    /// separate outcome. A cancelled attempt counts toward neither the breaker's
+    /// successes nor its failures, but the time it ran for still feeds the hedge,
+    /// like the latency of every completed attempt, successful or not. No
+    /// second attempt is made unless the circuit is closed, so that hedging never
+    /// spends half-open probes. Only use this for idempotent operations.
+    ///
+    /// ```rust,ignore
+    /// let hedge = Hedge::percentile(0.95, Duration::from_millis(50));
+    ///
+    /// let result = breaker.call_async_hedged(&hedge, || client.get(key)).await;
+    /// ```
+    pub async fn call_async_hedged<F, Fut, T>(&self, hedge: &Hedge, f: F) -> BreakerResult<T, E>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        use futures::future::{select, Either};
+
+        let attempt = || async {
+            let timer = hedge.start_attempt();
+            let result = self.call_async(&f).await;
+            // Rejected attempts never ran, so they tell nothing about latency
+            timer.finish(matches!(result, Ok(_) | Err(BreakerError::Operation(_))));
+            result
+        };
+
+        let first = attempt();
+        tokio::pin!(first);
+        if let Ok(result) = tokio::time::timeout(hedge.delay(), &mut first).await {
+            return result;
+        }
+        if self.current_state() != State::Closed {
+            return first.await;
+        }
+
+        let second = attempt();
+        tokio::pin!(second);
+        match select(first, second).await {
+            Either::Left((Ok(value), _)) | Either::Right((Ok(value), _)) => Ok(value),
+            // Fall back to whichever attempt is still running
+            Either::Left((Err(_), other)) => other.await,
+            Either::Right((Err(_), other)) => other.await,
+        }
+    }
 }
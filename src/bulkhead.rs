@@ -0,0 +1,130 @@
+//! Bulkhead bounding the number of concurrent calls through a breaker.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// A semaphore-style bulkhead that sync and async calls share.
+///
+/// Calls take a slot if one is free. Otherwise they wait up to `max_wait` for
+/// one to be released, or are rejected right away if `max_wait` is zero.
+pub(crate) struct Bulkhead {
+    max_calls: usize,
+    max_wait: Duration,
+    in_flight: AtomicUsize,
+    // Held while checking for a slot before waiting, so that no release is missed
+    lock: parking_lot::Mutex<()>,
+    available: parking_lot::Condvar,
+    #[cfg(feature = "async")]
+    notify: tokio::sync::Notify,
+}
+
+impl Bulkhead {
+    /// Creates a bulkhead allowing `max_calls` concurrent calls.
+    pub(crate) fn new(max_calls: usize, max_wait: Duration) -> Self {
+        Self {
+            max_calls,
+            max_wait,
+            in_flight: AtomicUsize::new(0),
+            lock: parking_lot::Mutex::new(()),
+            available: parking_lot::Condvar::new(),
+            #[cfg(feature = "async")]
+            notify: tokio::sync::Notify::new(),
+        }
+    }
+
+    /// Gets the number of calls currently holding a slot.
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Takes a slot if one is free.
+    fn try_acquire(&self) -> bool {
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |n| {
+                (n < self.max_calls).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    /// Takes a slot, blocking the thread for up to the maximum wait time.
+    pub(crate) fn acquire(&self) -> bool {
+        if self.try_acquire() {
+            return true;
+        }
+        if self.max_wait.is_zero() {
+            return false;
+        }
+
+        let deadline = Instant::now() + self.max_wait;
+        let mut guard = self.lock.lock();
+        loop {
+            if self.try_acquire() {
+                return true;
+            }
+            if self.available.wait_until(&mut guard, deadline).timed_out() {
+                return self.try_acquire();
+            }
+        }
+    }
+
+    /// Takes a slot, waiting asynchronously for up to the maximum wait time.
+    ///
+    /// Waiting relies on the Tokio timer.
+    #[cfg(feature = "async")]
+    pub(crate) async fn acquire_async(&self) -> bool {
+        if self.try_acquire() {
+            return true;
+        }
+        if self.max_wait.is_zero() {
+            return false;
+        }
+
+        let deadline = tokio::time::Instant::now() + self.max_wait;
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // Register before checking, so that a release in between wakes us
+            notified.as_mut().enable();
+            if self.try_acquire() {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.try_acquire();
+            }
+        }
+    }
+
+    /// Releases a slot and wakes a waiting call.
+    pub(crate) fn release(&self) {
+        self.in_flight.fetch_sub(1, Ordering::AcqRel);
+        if self.max_wait.is_zero() {
+            return;
+        }
+
+        drop(self.lock.lock());
+        self.available.notify_one();
+        #[cfg(feature = "async")]
+        self.notify.notify_one();
+    }
+
+    /// Takes a slot like [`Bulkhead::acquire`], returning a guard that releases it.
+    pub(crate) fn enter(&self) -> Option<BulkheadSlot<'_>> {
+        self.acquire().then_some(BulkheadSlot(self))
+    }
+
+    /// Takes a slot like [`Bulkhead::acquire_async`], returning a guard that
+    /// releases it.
+    #[cfg(feature = "async")]
+    pub(crate) async fn enter_async(&self) -> Option<BulkheadSlot<'_>> {
+        self.acquire_async().await.then_some(BulkheadSlot(self))
+    }
+}
+
+/// A slot taken in a [`Bulkhead`], released on drop.
+pub(crate) struct BulkheadSlot<'a>(&'a Bulkhead);
+
+impl Drop for BulkheadSlot<'_> {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
@@ -0,0 +1,210 @@
+//! Combinators for composing circuit breaker policies.
+
+use crate::metrics::StatsView;
+use crate::policy::{BreakerPolicy, PolicyThresholds};
+use std::time::Duration;
+
+/// A policy that trips when either policy trips, and resets only when both agree.
+///
+/// A call is admitted only if both policies admit it. Created with [`PolicyExt::or`].
+#[derive(Debug, Clone)]
+pub struct AnyOf<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> AnyOf<A, B> {
+    /// Combines two policies so that either can trip the circuit.
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+/// A policy that trips only when both policies agree, and resets when either does.
+///
+/// A call is admitted if either policy admits it. Created with [`PolicyExt::and`].
+#[derive(Debug, Clone)]
+pub struct AllOf<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> AllOf<A, B> {
+    /// Combines two policies so that both must agree to trip the circuit.
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+/// A policy that inverts both the trip and the reset decision of another policy.
+///
+/// Admission decisions are passed through unchanged. Created with [`PolicyExt::not`].
+#[derive(Debug, Clone)]
+pub struct Not<P> {
+    inner: P,
+}
+
+impl<P> Not<P> {
+    /// Inverts the decisions of a policy.
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+/// Combinator methods available on every [`BreakerPolicy`].
+///
+/// Outcome observations and clears are forwarded to every combined policy, so
+/// windowed policies keep tracking calls even when another policy decides.
+///
+/// ```rust
+/// use circuitbreaker_rs::{DefaultPolicy, PolicyExt, TimeBasedPolicy};
+///
+/// // Trip on a high overall error rate or a burst of recent failures
+/// let policy = DefaultPolicy::new(0.5, 10, 5, 3).or(TimeBasedPolicy::builder().build());
+/// ```
+pub trait PolicyExt: BreakerPolicy + Sized {
+    /// Trips when either policy trips, and resets only when both agree.
+    fn or<Q: BreakerPolicy>(self, other: Q) -> AnyOf<Self, Q> {
+        AnyOf::new(self, other)
+    }
+
+    /// Trips only when both policies trip, and resets when either agrees.
+    fn and<Q: BreakerPolicy>(self, other: Q) -> AllOf<Self, Q> {
+        AllOf::new(self, other)
+    }
+
+    /// Inverts both the trip and the reset decision.
+    fn not(self) -> Not<Self> {
+        Not::new(self)
+    }
+}
+
+impl<P: BreakerPolicy> PolicyExt for P {}
+
+impl<A, B> BreakerPolicy for AnyOf<A, B>
+where
+    A: BreakerPolicy,
+    B: BreakerPolicy,
+{
+    fn should_trip(&self, stats: &StatsView) -> bool {
+        self.first.should_trip(stats) || self.second.should_trip(stats)
+    }
+
+    fn should_reset(&self, stats: &StatsView) -> bool {
+        self.first.should_reset(stats) && self.second.should_reset(stats)
+    }
+
+    fn admit(&self) -> bool {
+        // Both are asked so that each sees every call
+        let first = self.first.admit();
+        let second = self.second.admit();
+        first && second
+    }
+
+    fn record_outcome(&self, success: bool, duration: Duration) {
+        self.first.record_outcome(success, duration);
+        self.second.record_outcome(success, duration);
+    }
+
+    fn trips_on_success(&self) -> bool {
+        self.first.trips_on_success() || self.second.trips_on_success()
+    }
+
+    fn clear(&self) {
+        self.first.clear();
+        self.second.clear();
+    }
+
+    fn thresholds(&self) -> Option<PolicyThresholds> {
+        self.first.thresholds().or_else(|| self.second.thresholds())
+    }
+
+    fn update_thresholds(&self, thresholds: PolicyThresholds) {
+        update_reported(&self.first, &self.second, thresholds);
+    }
+}
+
+impl<A, B> BreakerPolicy for AllOf<A, B>
+where
+    A: BreakerPolicy,
+    B: BreakerPolicy,
+{
+    fn should_trip(&self, stats: &StatsView) -> bool {
+        self.first.should_trip(stats) && self.second.should_trip(stats)
+    }
+
+    fn should_reset(&self, stats: &StatsView) -> bool {
+        self.first.should_reset(stats) || self.second.should_reset(stats)
+    }
+
+    fn admit(&self) -> bool {
+        // Both are asked so that each sees every call
+        let first = self.first.admit();
+        let second = self.second.admit();
+        first || second
+    }
+
+    fn record_outcome(&self, success: bool, duration: Duration) {
+        self.first.record_outcome(success, duration);
+        self.second.record_outcome(success, duration);
+    }
+
+    fn trips_on_success(&self) -> bool {
+        self.first.trips_on_success() || self.second.trips_on_success()
+    }
+
+    fn clear(&self) {
+        self.first.clear();
+        self.second.clear();
+    }
+
+    fn thresholds(&self) -> Option<PolicyThresholds> {
+        self.first.thresholds().or_else(|| self.second.thresholds())
+    }
+
+    fn update_thresholds(&self, thresholds: PolicyThresholds) {
+        update_reported(&self.first, &self.second, thresholds);
+    }
+}
+
+/// Updates the thresholds of the policy whose thresholds a combinator reports,
+/// leaving those of the other policy as they are.
+fn update_reported<A, B>(first: &A, second: &B, thresholds: PolicyThresholds)
+where
+    A: BreakerPolicy,
+    B: BreakerPolicy,
+{
+    if first.thresholds().is_some() {
+        first.update_thresholds(thresholds);
+    } else {
+        second.update_thresholds(thresholds);
+    }
+}
+
+impl<P: BreakerPolicy> BreakerPolicy for Not<P> {
+    fn should_trip(&self, stats: &StatsView) -> bool {
+        !self.inner.should_trip(stats)
+    }
+
+    fn should_reset(&self, stats: &StatsView) -> bool {
+        !self.inner.should_reset(stats)
+    }
+
+    fn admit(&self) -> bool {
+        // Shedding is not a decision that can be meaningfully inverted
+        self.inner.admit()
+    }
+
+    fn record_outcome(&self, success: bool, duration: Duration) {
+        self.inner.record_outcome(success, duration);
+    }
+
+    fn trips_on_success(&self) -> bool {
+        // Successes can make the inner policy stop wanting to trip
+        true
+    }
+
+    fn clear(&self) {
+        self.inner.clear();
+    }
+}
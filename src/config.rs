@@ -4,13 +4,84 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::breaker::CircuitBreaker;
+use crate::breaker::{CallLimits, CircuitBreaker};
+use crate::bulkhead::Bulkhead;
+use crate::error::ConfigError;
 use crate::hook::HookRegistry;
-use crate::metrics::{MetricSink, NullMetricSink};
-use crate::policy::{BreakerPolicy, DefaultPolicy};
+use crate::limiter::{AdaptiveLimiter, AdaptiveLimiterBuilder};
+use crate::metrics::{BreakerStats, CountWindow, MetricSink, NullMetricSink};
+use crate::policy::{
+    AdaptiveThrottlePolicy, AdaptiveThrottlePolicyBuilder, BoxedPolicy, BreakerPolicy,
+    DefaultPolicy, LatencyPolicy, LatencyPolicyBuilder, PolicyThresholds, SloBurnRatePolicy,
+    SloBurnRatePolicyBuilder, ThroughputAwarePolicy, ThroughputAwarePolicyBuilder, TimeBasedPolicy,
+    TimeBasedPolicyBuilder,
+};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryBudget;
+
+/// Settings of a running circuit breaker that can be changed without rebuilding it.
+///
+/// Obtained from [`CircuitBreaker::config`] and applied with
+/// [`CircuitBreaker::update_config`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuntimeConfig {
+    /// Cooldown duration before the circuit transitions from open to half-open.
+    pub cooldown: Duration,
+
+    /// Number of probes allowed in half-open state.
+    pub probe_interval: u32,
+
+    /// Thresholds of the breaker's policy, or `None` if the policy cannot be
+    /// reconfigured at runtime.
+    pub thresholds: Option<PolicyThresholds>,
+}
+
+impl RuntimeConfig {
+    /// Checks that every setting is within its valid range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = ConfigError::new();
+        self.check(&mut errors);
+        errors.into_result()
+    }
+
+    /// Records every out-of-range setting in `errors`.
+    fn check(&self, errors: &mut ConfigError) {
+        if self.probe_interval == 0 {
+            errors.push("probe_interval", "must be at least 1");
+        }
+        if let Some(thresholds) = &self.thresholds {
+            thresholds.check(errors);
+        }
+    }
+}
+
+pub(crate) const DEFAULT_FAILURE_THRESHOLD: f64 = 0.5;
+pub(crate) const DEFAULT_MIN_THROUGHPUT: u64 = 10;
+pub(crate) const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+pub(crate) const DEFAULT_PROBE_INTERVAL: u32 = 5;
+pub(crate) const DEFAULT_CONSECUTIVE_FAILURES: u64 = 5;
+pub(crate) const DEFAULT_CONSECUTIVE_SUCCESSES: u64 = 3;
+
+/// Every policy decision sums all stripes, so the read cost grows with each one;
+/// 64 stripes already spread a contended breaker over 4 KiB of counters.
+const MAX_COUNTER_SHARDS: usize = 64;
+
+/// Typestate of a [`BreakerBuilder`] that builds breakers with the default policy.
+pub struct NoPolicy;
+
+/// Typestate of a [`BreakerBuilder`] that holds a custom policy, or the errors
+/// found while building it.
+pub struct WithPolicy<P>(Result<P, ConfigError>);
 
 /// Builder for creating circuit breakers with custom configurations.
-pub struct BreakerBuilder<P, E>
+///
+/// The `S` parameter tracks whether a custom policy was supplied: [`build`] is
+/// available until [`policy`] is called, and [`build_with_policy`] only after.
+///
+/// [`build`]: BreakerBuilder::build
+/// [`policy`]: BreakerBuilder::policy
+/// [`build_with_policy`]: BreakerBuilder::build_with_policy
+pub struct BreakerBuilder<P, E, S = NoPolicy>
 where
     P: BreakerPolicy,
     E: std::error::Error + 'static,
@@ -21,10 +92,18 @@ where
     probe_interval: u32,
     consecutive_failures_threshold: u64,
     consecutive_successes_threshold: u64,
-    policy: Option<P>,
+    sliding_window: Option<usize>,
+    counter_shards: usize,
+    max_concurrent_calls: Option<usize>,
+    max_queue_time: Duration,
+    limiter: Option<Result<AdaptiveLimiter, ConfigError>>,
+    rate_limit: Option<(u32, Duration)>,
+    rate_limit_burst: Option<u32>,
+    retry_budget: Option<Arc<RetryBudget>>,
+    policy: S,
     metric_sink: Arc<dyn MetricSink>,
     hook_registry: Arc<HookRegistry>,
-    _error_type: PhantomData<E>,
+    _types: PhantomData<(P, E)>,
 }
 
 impl<E> Default for BreakerBuilder<DefaultPolicy, E>
@@ -42,26 +121,59 @@ where
 {
     /// Creates a new builder with default settings.
     pub fn new() -> Self {
-        Self {
-            failure_threshold: 0.5,
-            min_throughput: 10,
-            cooldown_duration: Duration::from_secs(30),
-            probe_interval: 5,
-            consecutive_failures_threshold: 5,
-            consecutive_successes_threshold: 3,
-            policy: None,
-            metric_sink: Arc::new(NullMetricSink),
-            hook_registry: Arc::new(HookRegistry::new()),
-            _error_type: PhantomData,
-        }
+        Self::with_policy_slot(NoPolicy)
+    }
+
+    /// Makes the default policy judge the error rate over the last `calls` calls
+    /// instead of every call since the statistics were last reset.
+    ///
+    /// See [`DefaultPolicy::with_sliding_window`]. Building fails with an invalid
+    /// `sliding_window` if a custom policy is chosen afterwards.
+    pub fn sliding_window(mut self, calls: usize) -> Self {
+        self.sliding_window = Some(calls);
+        self
     }
 }
 
-impl<P, E> BreakerBuilder<P, E>
+impl<P, E> BreakerBuilder<P, E, WithPolicy<P>>
 where
     P: BreakerPolicy,
     E: std::error::Error + 'static,
 {
+    /// Creates a builder with default settings that builds breakers using `policy`.
+    pub fn with_policy(policy: P) -> Self {
+        Self::with_policy_slot(WithPolicy(Ok(policy)))
+    }
+}
+
+impl<P, E, S> BreakerBuilder<P, E, S>
+where
+    P: BreakerPolicy,
+    E: std::error::Error + 'static,
+{
+    fn with_policy_slot(policy: S) -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            min_throughput: DEFAULT_MIN_THROUGHPUT,
+            cooldown_duration: DEFAULT_COOLDOWN,
+            probe_interval: DEFAULT_PROBE_INTERVAL,
+            consecutive_failures_threshold: DEFAULT_CONSECUTIVE_FAILURES,
+            consecutive_successes_threshold: DEFAULT_CONSECUTIVE_SUCCESSES,
+            sliding_window: None,
+            counter_shards: 1,
+            max_concurrent_calls: None,
+            max_queue_time: Duration::ZERO,
+            limiter: None,
+            rate_limit: None,
+            rate_limit_burst: None,
+            retry_budget: None,
+            policy,
+            metric_sink: Arc::new(NullMetricSink),
+            hook_registry: Arc::new(HookRegistry::new()),
+            _types: PhantomData,
+        }
+    }
+
     /// Sets the failure rate threshold that will trip the circuit.
     pub fn failure_threshold(mut self, threshold: f64) -> Self {
         self.failure_threshold = threshold;
@@ -98,12 +210,209 @@ where
         self
     }
 
-    /// Sets a custom policy for the circuit breaker.
-    pub fn policy(mut self, policy: P) -> Self {
-        self.policy = Some(policy);
+    /// Stripes the breaker's call counters across `shards` cache lines.
+    ///
+    /// Each thread records into one stripe and reads add them up, which avoids
+    /// contention when many cores call the same breaker. A good value is the number
+    /// of cores, e.g. from [`std::thread::available_parallelism`], up to 64.
+    /// Defaults to 1.
+    pub fn counter_shards(mut self, shards: usize) -> Self {
+        self.counter_shards = shards;
         self
     }
 
+    /// Bounds the number of calls running through the breaker at the same time.
+    ///
+    /// Once `calls` calls are running, further calls wait for up to the
+    /// [`max_queue_time`] and then fail with [`BreakerError::BulkheadFull`].
+    ///
+    /// [`max_queue_time`]: BreakerBuilder::max_queue_time
+    /// [`BreakerError::BulkheadFull`]: crate::BreakerError::BulkheadFull
+    pub fn max_concurrent_calls(mut self, calls: usize) -> Self {
+        self.max_concurrent_calls = Some(calls);
+        self
+    }
+
+    /// Sets how long a call may wait for a slot when the bulkhead is full.
+    ///
+    /// `call` blocks the thread while waiting and `call_async` waits on the Tokio
+    /// timer. Defaults to zero, which rejects calls immediately. Ignored without
+    /// [`BreakerBuilder::max_concurrent_calls`].
+    pub fn max_queue_time(mut self, duration: Duration) -> Self {
+        self.max_queue_time = duration;
+        self
+    }
+
+    /// Limits the rate of calls to `calls` calls every `period`.
+    ///
+    /// Calls over the rate fail with [`BreakerError::RateLimited`] without being
+    /// attempted. By default a whole period's worth of calls may be made at once;
+    /// see [`BreakerBuilder::rate_limit_burst`].
+    ///
+    /// ```rust
+    /// # use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+    /// # use std::time::Duration;
+    /// // At most 100 calls per second, in bursts of up to 10
+    /// let breaker = CircuitBreaker::<DefaultPolicy, std::io::Error>::builder()
+    ///     .rate_limit(100, Duration::from_secs(1))
+    ///     .rate_limit_burst(10)
+    ///     .build();
+    /// ```
+    ///
+    /// [`BreakerError::RateLimited`]: crate::BreakerError::RateLimited
+    pub fn rate_limit(mut self, calls: u32, period: Duration) -> Self {
+        self.rate_limit = Some((calls, period));
+        self
+    }
+
+    /// Sets the number of calls that may be made at once under the rate limit.
+    ///
+    /// Ignored without [`BreakerBuilder::rate_limit`].
+    pub fn rate_limit_burst(mut self, calls: u32) -> Self {
+        self.rate_limit_burst = Some(calls);
+        self
+    }
+
+    /// Limits concurrent calls with an [`AdaptiveLimiter`] configured by `f`.
+    ///
+    /// Calls over the limit fail with [`BreakerError::LimitExceeded`]. Invalid
+    /// limiter settings are reported when the breaker is built.
+    ///
+    /// ```rust
+    /// # use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy, LimitAlgorithm};
+    /// let breaker = CircuitBreaker::<DefaultPolicy, std::io::Error>::builder()
+    ///     .adaptive_limit(|l| {
+    ///         l.algorithm(LimitAlgorithm::Gradient { smoothing: 0.2 })
+    ///             .max_limit(100)
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// [`BreakerError::LimitExceeded`]: crate::BreakerError::LimitExceeded
+    pub fn adaptive_limit<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(AdaptiveLimiterBuilder) -> AdaptiveLimiterBuilder,
+    {
+        self.limiter = Some(f(AdaptiveLimiter::builder()).try_build());
+        self
+    }
+
+    /// Attaches a retry budget, which may be shared with other breakers.
+    ///
+    /// Successful calls through the breaker deposit into the budget, and
+    /// [`Retry`] withdraws from it before each retry.
+    ///
+    /// [`Retry`]: crate::Retry
+    pub fn retry_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    /// Sets a custom policy for the circuit breaker.
+    ///
+    /// The breaker must then be built with [`BreakerBuilder::build_with_policy`].
+    pub fn policy<Q: BreakerPolicy>(self, policy: Q) -> BreakerBuilder<Q, E, WithPolicy<Q>> {
+        self.map_policy(|_| WithPolicy(Ok(policy)))
+    }
+
+    /// Uses a [`TimeBasedPolicy`] configured by `f`.
+    ///
+    /// The policy builder starts from this builder's failure threshold, minimum
+    /// throughput and consecutive success count. Invalid policy settings are
+    /// reported when the breaker is built.
+    ///
+    /// ```rust
+    /// # use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+    /// # use std::time::Duration;
+    /// let breaker = CircuitBreaker::<DefaultPolicy, std::io::Error>::builder()
+    ///     .failure_threshold(0.25)
+    ///     .time_based(|p| p.window(Duration::from_secs(10)).buckets(5))
+    ///     .build_with_policy();
+    /// ```
+    pub fn time_based<F>(
+        self,
+        f: F,
+    ) -> BreakerBuilder<TimeBasedPolicy, E, WithPolicy<TimeBasedPolicy>>
+    where
+        F: FnOnce(TimeBasedPolicyBuilder) -> TimeBasedPolicyBuilder,
+    {
+        let policy = f(TimeBasedPolicy::builder()
+            .failure_threshold(self.failure_threshold)
+            .min_calls(self.min_throughput)
+            .consecutive_successes(self.consecutive_successes_threshold))
+        .try_build();
+        self.map_policy(|_| WithPolicy(policy))
+    }
+
+    /// Uses a [`ThroughputAwarePolicy`] configured by `f`.
+    ///
+    /// The policy builder starts from this builder's failure threshold. Invalid
+    /// policy settings are reported when the breaker is built.
+    pub fn throughput_aware<F>(
+        self,
+        f: F,
+    ) -> BreakerBuilder<ThroughputAwarePolicy, E, WithPolicy<ThroughputAwarePolicy>>
+    where
+        F: FnOnce(ThroughputAwarePolicyBuilder) -> ThroughputAwarePolicyBuilder,
+    {
+        let policy = f(ThroughputAwarePolicy::builder().failure_threshold(self.failure_threshold))
+            .try_build();
+        self.map_policy(|_| WithPolicy(policy))
+    }
+
+    /// Uses a [`LatencyPolicy`] configured by `f`.
+    ///
+    /// Invalid policy settings are reported when the breaker is built.
+    ///
+    /// ```rust
+    /// # use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+    /// # use std::time::Duration;
+    /// // Trip when the 99th percentile of the last 10 seconds exceeds 2 seconds
+    /// let breaker = CircuitBreaker::<DefaultPolicy, std::io::Error>::builder()
+    ///     .latency(|p| p.percentile(0.99).threshold(Duration::from_secs(2)))
+    ///     .build_with_policy();
+    /// ```
+    pub fn latency<F>(self, f: F) -> BreakerBuilder<LatencyPolicy, E, WithPolicy<LatencyPolicy>>
+    where
+        F: FnOnce(LatencyPolicyBuilder) -> LatencyPolicyBuilder,
+    {
+        let policy = f(LatencyPolicy::builder()).try_build();
+        self.map_policy(|_| WithPolicy(policy))
+    }
+
+    /// Uses an [`SloBurnRatePolicy`] configured by `f`.
+    ///
+    /// The policy builder starts from this builder's minimum throughput and
+    /// consecutive success count. Invalid policy settings are reported when the
+    /// breaker is built.
+    pub fn slo_burn_rate<F>(
+        self,
+        f: F,
+    ) -> BreakerBuilder<SloBurnRatePolicy, E, WithPolicy<SloBurnRatePolicy>>
+    where
+        F: FnOnce(SloBurnRatePolicyBuilder) -> SloBurnRatePolicyBuilder,
+    {
+        let policy = f(SloBurnRatePolicy::builder()
+            .min_calls(self.min_throughput)
+            .consecutive_successes(self.consecutive_successes_threshold))
+        .try_build();
+        self.map_policy(|_| WithPolicy(policy))
+    }
+
+    /// Uses an [`AdaptiveThrottlePolicy`] configured by `f`.
+    ///
+    /// Invalid policy settings are reported when the breaker is built.
+    pub fn adaptive_throttle<F>(
+        self,
+        f: F,
+    ) -> BreakerBuilder<AdaptiveThrottlePolicy, E, WithPolicy<AdaptiveThrottlePolicy>>
+    where
+        F: FnOnce(AdaptiveThrottlePolicyBuilder) -> AdaptiveThrottlePolicyBuilder,
+    {
+        let policy = f(AdaptiveThrottlePolicy::builder()).try_build();
+        self.map_policy(|_| WithPolicy(policy))
+    }
+
     /// Sets a metric sink for the circuit breaker.
     pub fn metric_sink<M: MetricSink>(mut self, sink: M) -> Self {
         self.metric_sink = Arc::new(sink);
@@ -117,7 +426,16 @@ where
     }
 
     /// Changes the error type for the builder.
-    pub fn with_error_type<NewE: std::error::Error + 'static>(self) -> BreakerBuilder<P, NewE> {
+    pub fn with_error_type<NewE: std::error::Error + 'static>(self) -> BreakerBuilder<P, NewE, S> {
+        self.map_policy(|policy| policy)
+    }
+
+    fn map_policy<Q, NewE, T, F>(self, f: F) -> BreakerBuilder<Q, NewE, T>
+    where
+        Q: BreakerPolicy,
+        NewE: std::error::Error + 'static,
+        F: FnOnce(S) -> T,
+    {
         BreakerBuilder {
             failure_threshold: self.failure_threshold,
             min_throughput: self.min_throughput,
@@ -125,26 +443,121 @@ where
             probe_interval: self.probe_interval,
             consecutive_failures_threshold: self.consecutive_failures_threshold,
             consecutive_successes_threshold: self.consecutive_successes_threshold,
-            policy: self.policy,
+            sliding_window: self.sliding_window,
+            counter_shards: self.counter_shards,
+            max_concurrent_calls: self.max_concurrent_calls,
+            max_queue_time: self.max_queue_time,
+            limiter: self.limiter,
+            rate_limit: self.rate_limit,
+            rate_limit_burst: self.rate_limit_burst,
+            retry_budget: self.retry_budget,
+            policy: f(self.policy),
             metric_sink: self.metric_sink,
             hook_registry: self.hook_registry,
-            _error_type: PhantomData,
+            _types: PhantomData,
+        }
+    }
+
+    /// Records every invalid setting shared by all policies in `errors`.
+    fn check(&self, errors: &mut ConfigError) {
+        RuntimeConfig {
+            cooldown: self.cooldown_duration,
+            probe_interval: self.probe_interval,
+            thresholds: None,
+        }
+        .check(errors);
+        if self.counter_shards == 0 {
+            errors.push("counter_shards", "must be at least 1");
+        } else if self.counter_shards > MAX_COUNTER_SHARDS {
+            errors.push(
+                "counter_shards",
+                format!("must be at most {}", MAX_COUNTER_SHARDS),
+            );
+        }
+        if self.max_concurrent_calls == Some(0) {
+            errors.push("max_concurrent_calls", "must be at least 1");
+        }
+        if let Some((calls, period)) = self.rate_limit {
+            if calls == 0 || period.is_zero() {
+                errors.push(
+                    "rate_limit",
+                    "must allow at least one call in a non-zero period",
+                );
+            }
+        }
+        if self.rate_limit_burst == Some(0) {
+            errors.push("rate_limit_burst", "must be at least 1");
+        }
+        if let Some(Err(limiter_errors)) = &self.limiter {
+            errors.extend(limiter_errors.clone());
         }
     }
 
-    /// Builds a new circuit breaker with the configured settings.
-    /// This method is available only for non-DefaultPolicy implementations.
+    /// Takes the configured limits on concurrent calls for a new breaker.
+    fn call_limits(&mut self) -> CallLimits {
+        CallLimits {
+            bulkhead: self
+                .max_concurrent_calls
+                .map(|calls| Bulkhead::new(calls, self.max_queue_time)),
+            limiter: self.limiter.take().and_then(Result::ok),
+            rate_limiter: self.rate_limit.map(|(calls, period)| {
+                RateLimiter::new(calls, period, self.rate_limit_burst.unwrap_or(calls))
+            }),
+            retry_budget: self.retry_budget.take(),
+        }
+    }
+}
+
+impl<P, E> BreakerBuilder<P, E, WithPolicy<P>>
+where
+    P: BreakerPolicy,
+    E: std::error::Error + 'static,
+{
+    /// Builds a new circuit breaker with the custom policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`BreakerBuilder::try_build_with_policy`] to handle invalid values.
     pub fn build_with_policy(self) -> CircuitBreaker<P, E> {
-        match self.policy {
-            Some(policy) => CircuitBreaker::new(
-                policy,
-                self.cooldown_duration,
-                self.probe_interval,
-                self.metric_sink,
-                self.hook_registry,
-            ),
-            None => panic!("Policy must be provided when not using DefaultPolicy"),
+        self.try_build_with_policy()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds a new circuit breaker with the custom policy, or returns every
+    /// invalid setting.
+    pub fn try_build_with_policy(mut self) -> Result<CircuitBreaker<P, E>, ConfigError> {
+        let mut errors = ConfigError::new();
+        self.check(&mut errors);
+        let limits = self.call_limits();
+        let policy = match self.policy.0 {
+            Ok(policy) => policy,
+            Err(policy_errors) => {
+                errors.extend(policy_errors);
+                return Err(errors);
+            }
+        };
+        if self.sliding_window.is_some() {
+            errors.push("sliding_window", "only applies to the default policy");
         }
+        errors.into_result()?;
+
+        Ok(CircuitBreaker::with_stats(
+            policy,
+            self.cooldown_duration,
+            self.probe_interval,
+            self.metric_sink,
+            self.hook_registry,
+            BreakerStats::with_shards(self.counter_shards),
+            limits,
+        ))
+    }
+
+    /// Boxes the custom policy, so that breakers with different policies share one type.
+    pub fn boxed(self) -> BreakerBuilder<BoxedPolicy, E, WithPolicy<BoxedPolicy>> {
+        self.map_policy(|WithPolicy(policy)| {
+            WithPolicy(policy.map(|policy| Box::new(policy) as BoxedPolicy))
+        })
     }
 }
 
@@ -152,8 +565,43 @@ impl<E> BreakerBuilder<DefaultPolicy, E>
 where
     E: std::error::Error + 'static,
 {
-    /// Builds a circuit breaker with the default policy.
-    pub fn build(self) -> CircuitBreaker<DefaultPolicy, E> {
+    /// Boxes the default policy, so that breakers with different policies share one type.
+    pub fn boxed(mut self) -> BreakerBuilder<BoxedPolicy, E, WithPolicy<BoxedPolicy>> {
+        // Settings shared by all policies are still checked at build time
+        let mut errors = ConfigError::new();
+        self.check_default_policy(&mut errors);
+        let policy = errors
+            .into_result()
+            .map(|()| Box::new(self.default_policy()) as BoxedPolicy);
+        // The window now belongs to the boxed policy, or is reported with it
+        self.sliding_window = None;
+        self.map_policy(|_| WithPolicy(policy))
+    }
+
+    /// Records every invalid setting of the default policy in `errors`.
+    fn check_default_policy(&self, errors: &mut ConfigError) {
+        PolicyThresholds {
+            failure_threshold: self.failure_threshold,
+            min_throughput: self.min_throughput,
+            consecutive_failures: self.consecutive_failures_threshold,
+            consecutive_successes: self.consecutive_successes_threshold,
+        }
+        .check(errors);
+        if let Some(size) = self.sliding_window {
+            CountWindow::check(errors, "sliding_window", size);
+        }
+    }
+
+    /// Checks every configured value, collecting all invalid fields.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = ConfigError::new();
+        self.check(&mut errors);
+        self.check_default_policy(&mut errors);
+        errors.into_result()
+    }
+
+    /// Creates the default policy from the configured thresholds.
+    fn default_policy(&self) -> DefaultPolicy {
         let policy = DefaultPolicy::new(
             self.failure_threshold,
             self.min_throughput,
@@ -161,12 +609,36 @@ where
             self.consecutive_successes_threshold,
         );
 
-        CircuitBreaker::new(
-            policy,
+        match self.sliding_window {
+            Some(size) => policy.with_sliding_window(size),
+            None => policy,
+        }
+    }
+
+    /// Builds a circuit breaker with the default policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use [`BreakerBuilder::try_build`]
+    /// to handle invalid values.
+    pub fn build(self) -> CircuitBreaker<DefaultPolicy, E> {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds a circuit breaker with the default policy, or returns every
+    /// invalid setting.
+    pub fn try_build(mut self) -> Result<CircuitBreaker<DefaultPolicy, E>, ConfigError> {
+        self.validate()?;
+        let limits = self.call_limits();
+
+        Ok(CircuitBreaker::with_stats(
+            self.default_policy(),
             self.cooldown_duration,
             self.probe_interval,
             self.metric_sink,
             self.hook_registry,
-        )
+            BreakerStats::with_shards(self.counter_shards),
+            limits,
+        ))
     }
 }
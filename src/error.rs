@@ -12,6 +12,18 @@ pub enum BreakerError<E> {
     /// The circuit is open, calls are not permitted.
     Open,
 
+    /// The call was shed by the policy's adaptive throttling while the circuit was closed.
+    Throttled,
+
+    /// The breaker's concurrency limit was reached.
+    LimitExceeded,
+
+    /// Every slot of the breaker's bulkhead was taken.
+    BulkheadFull,
+
+    /// The breaker's rate limit was exceeded.
+    RateLimited,
+
     /// The underlying operation failed.
     Operation(E),
 
@@ -42,6 +54,10 @@ where
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             BreakerError::Open => write!(f, "Circuit breaker is open"),
+            BreakerError::Throttled => write!(f, "Call throttled by circuit breaker"),
+            BreakerError::LimitExceeded => write!(f, "Circuit breaker concurrency limit exceeded"),
+            BreakerError::BulkheadFull => write!(f, "Circuit breaker bulkhead is full"),
+            BreakerError::RateLimited => write!(f, "Circuit breaker rate limit exceeded"),
             BreakerError::Operation(e) => write!(f, "Operation error: {}", e),
             BreakerError::Internal(e) => write!(f, "Circuit breaker internal error: {}", e),
         }
@@ -62,7 +78,11 @@ impl Display for InternalError {
 impl<E: Error + 'static> Error for BreakerError<E> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            BreakerError::Open => None,
+            BreakerError::Open
+            | BreakerError::Throttled
+            | BreakerError::LimitExceeded
+            | BreakerError::BulkheadFull
+            | BreakerError::RateLimited => None,
             BreakerError::Operation(e) => Some(e),
             BreakerError::Internal(_) => None,
         }
@@ -70,3 +90,162 @@ impl<E: Error + 'static> Error for BreakerError<E> {
 }
 
 impl Error for InternalError {}
+
+/// Result type for calls through a [`Pipeline`](crate::Pipeline).
+pub type PipelineResult<T, E> = Result<T, PipelineError<E>>;
+
+/// Error type for calls through a [`Pipeline`](crate::Pipeline).
+///
+/// Has every variant of [`BreakerError`], plus the errors added by the
+/// pipeline's own layers.
+#[derive(Debug)]
+pub enum PipelineError<E> {
+    /// The circuit is open, calls are not permitted.
+    Open,
+
+    /// The call was shed by the policy's adaptive throttling while the circuit was closed.
+    Throttled,
+
+    /// The breaker's concurrency limit was reached.
+    LimitExceeded,
+
+    /// Every slot of the breaker's or the pipeline's bulkhead was taken.
+    BulkheadFull,
+
+    /// The breaker's rate limit was exceeded.
+    RateLimited,
+
+    /// The operation did not complete within the pipeline's timeout.
+    Timeout,
+
+    /// The underlying operation failed.
+    Operation(E),
+
+    /// The circuit breaker encountered an internal error.
+    Internal(InternalError),
+}
+
+impl<E> From<BreakerError<E>> for PipelineError<E> {
+    fn from(error: BreakerError<E>) -> Self {
+        match error {
+            BreakerError::Open => PipelineError::Open,
+            BreakerError::Throttled => PipelineError::Throttled,
+            BreakerError::LimitExceeded => PipelineError::LimitExceeded,
+            BreakerError::BulkheadFull => PipelineError::BulkheadFull,
+            BreakerError::RateLimited => PipelineError::RateLimited,
+            BreakerError::Operation(e) => PipelineError::Operation(e),
+            BreakerError::Internal(e) => PipelineError::Internal(e),
+        }
+    }
+}
+
+impl<E> Display for PipelineError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::Open => write!(f, "Circuit breaker is open"),
+            PipelineError::Throttled => write!(f, "Call throttled by circuit breaker"),
+            PipelineError::LimitExceeded => {
+                write!(f, "Circuit breaker concurrency limit exceeded")
+            }
+            PipelineError::BulkheadFull => write!(f, "Bulkhead is full"),
+            PipelineError::RateLimited => write!(f, "Circuit breaker rate limit exceeded"),
+            PipelineError::Timeout => write!(f, "Operation timed out"),
+            PipelineError::Operation(e) => write!(f, "Operation error: {}", e),
+            PipelineError::Internal(e) => write!(f, "Circuit breaker internal error: {}", e),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for PipelineError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PipelineError::Operation(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned when a circuit breaker configuration is invalid.
+///
+/// Lists every invalid field rather than only the first one found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    invalid_fields: Vec<InvalidField>,
+}
+
+/// A configuration field with an invalid value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidField {
+    /// Name of the field.
+    pub field: &'static str,
+
+    /// Why the value was rejected.
+    pub reason: String,
+}
+
+impl ConfigError {
+    /// Creates an empty error to collect invalid fields into.
+    pub(crate) fn new() -> Self {
+        Self {
+            invalid_fields: Vec::new(),
+        }
+    }
+
+    /// Records an invalid field.
+    pub(crate) fn push(&mut self, field: &'static str, reason: impl Into<String>) {
+        self.invalid_fields.push(InvalidField {
+            field,
+            reason: reason.into(),
+        });
+    }
+
+    /// Appends the invalid fields of another error.
+    pub(crate) fn extend(&mut self, other: ConfigError) {
+        self.invalid_fields.extend(other.invalid_fields);
+    }
+
+    /// Returns `Ok(())` if no invalid fields were recorded, or the error otherwise.
+    pub(crate) fn into_result(self) -> Result<(), ConfigError> {
+        if self.invalid_fields.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Gets the invalid fields.
+    pub fn invalid_fields(&self) -> &[InvalidField] {
+        &self.invalid_fields
+    }
+
+    /// Returns true if the given field was rejected.
+    pub fn is_invalid(&self, field: &str) -> bool {
+        self.invalid_fields
+            .iter()
+            .any(|invalid| invalid.field == field)
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid circuit breaker configuration: ")?;
+        for (i, invalid) in self.invalid_fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", invalid)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for InvalidField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.reason)
+    }
+}
+
+impl Error for ConfigError {}
@@ -0,0 +1,286 @@
+//! Tower middleware for tonic gRPC clients, with a breaker per service or method.
+
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use http::{Request, Response};
+use tonic::{Code, Status};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::breaker::{BreakerInner, CircuitBreaker};
+use crate::error::BreakerError;
+use crate::policy::{BoxedPolicy, BreakerPolicy};
+use crate::registry::BreakerRegistry;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+type BreakerFactory<P> = Arc<dyn Fn(&str) -> Arc<BreakerInner<P>> + Send + Sync>;
+
+/// Which calls of a gRPC client share a breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrpcBreakerScope {
+    /// Every method of a service shares one breaker, keyed by `package.Service`.
+    #[default]
+    Service,
+
+    /// Every method has its own breaker, keyed by `package.Service/Method`.
+    Method,
+}
+
+/// A failed call, carried through the breaker to be handed back to the caller
+/// unchanged.
+struct Failure<B>(Result<Response<B>, BoxError>);
+
+impl<B> Debug for Failure<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Failure")
+    }
+}
+
+impl<B> Display for Failure<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("gRPC call failed")
+    }
+}
+
+impl<B> Error for Failure<B> {}
+
+/// A [`Layer`] sending the calls of a tonic client through circuit breakers.
+///
+/// Breakers are kept in a [`BreakerRegistry`], keyed by service or by method
+/// depending on the [`GrpcBreakerScope`], and created on the first call. Calls
+/// failing with `UNAVAILABLE`, `DEADLINE_EXCEEDED` or `RESOURCE_EXHAUSTED`, or
+/// with a transport error, are recorded as failures. Calls failing with
+/// `INVALID_ARGUMENT` are not recorded at all, and other calls are recorded as
+/// successes. Calls rejected by a breaker fail with `UNAVAILABLE`, or
+/// `RESOURCE_EXHAUSTED` when rate limited.
+///
+/// The status is read from the response headers, which hold it for calls that
+/// fail before sending a message. A status sent in the trailers of a streaming
+/// response is not seen by the layer.
+///
+/// A request is only handed to the channel once its breaker admitted it, by a
+/// clone of the channel that takes over the readiness of the original.
+///
+/// ```rust,ignore
+/// let registry = Arc::new(BreakerRegistry::new());
+/// let channel = ServiceBuilder::new()
+///     .layer(
+///         GrpcBreakerLayer::new(Arc::clone(&registry), |_key| {
+///             CircuitBreaker::<BoxedPolicy, Status>::builder().boxed().build_with_policy()
+///         })
+///         .scope(GrpcBreakerScope::Method),
+///     )
+///     .service(Channel::from_static("http://[::1]:50051").connect_lazy());
+/// let client = GreeterClient::new(channel);
+/// ```
+pub struct GrpcBreakerLayer<P = BoxedPolicy>
+where
+    P: BreakerPolicy,
+{
+    registry: Arc<BreakerRegistry<P>>,
+    factory: BreakerFactory<P>,
+    scope: GrpcBreakerScope,
+    failure_codes: Arc<[Code]>,
+    ignored_codes: Arc<[Code]>,
+}
+
+impl<P> GrpcBreakerLayer<P>
+where
+    P: BreakerPolicy,
+{
+    /// Creates a layer looking up breakers in `registry`, creating missing ones
+    /// with `factory`, which is given the key of the breaker.
+    pub fn new<E, F>(registry: Arc<BreakerRegistry<P>>, factory: F) -> Self
+    where
+        E: Error + 'static,
+        F: Fn(&str) -> CircuitBreaker<P, E> + Send + Sync + 'static,
+    {
+        Self {
+            registry,
+            factory: Arc::new(move |key| Arc::clone(factory(key).inner())),
+            scope: GrpcBreakerScope::default(),
+            failure_codes: Arc::new([
+                Code::Unavailable,
+                Code::DeadlineExceeded,
+                Code::ResourceExhausted,
+            ]),
+            ignored_codes: Arc::new([Code::InvalidArgument]),
+        }
+    }
+
+    /// Sets which calls share a breaker.
+    ///
+    /// Defaults to [`GrpcBreakerScope::Service`].
+    pub fn scope(mut self, scope: GrpcBreakerScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Sets the status codes recorded as failures.
+    pub fn failure_codes(mut self, codes: &[Code]) -> Self {
+        self.failure_codes = codes.into();
+        self
+    }
+
+    /// Sets the status codes of calls that are not recorded at all.
+    pub fn ignored_codes(mut self, codes: &[Code]) -> Self {
+        self.ignored_codes = codes.into();
+        self
+    }
+
+    /// Gets the registry holding the breakers.
+    pub fn registry(&self) -> &Arc<BreakerRegistry<P>> {
+        &self.registry
+    }
+}
+
+impl<P> Clone for GrpcBreakerLayer<P>
+where
+    P: BreakerPolicy,
+{
+    fn clone(&self) -> Self {
+        Self {
+            registry: Arc::clone(&self.registry),
+            factory: Arc::clone(&self.factory),
+            scope: self.scope,
+            failure_codes: Arc::clone(&self.failure_codes),
+            ignored_codes: Arc::clone(&self.ignored_codes),
+        }
+    }
+}
+
+impl<S, P> Layer<S> for GrpcBreakerLayer<P>
+where
+    P: BreakerPolicy,
+{
+    type Service = GrpcBreakerService<S, P>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcBreakerService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// A [`Service`] sending the calls of a tonic client through circuit breakers.
+///
+/// See [`GrpcBreakerLayer`].
+pub struct GrpcBreakerService<S, P = BoxedPolicy>
+where
+    P: BreakerPolicy,
+{
+    inner: S,
+    layer: GrpcBreakerLayer<P>,
+}
+
+impl<S, P> GrpcBreakerService<S, P>
+where
+    P: BreakerPolicy,
+{
+    /// Gets the breaker of the call to `path`, e.g. `/package.Service/Method`.
+    fn breaker<B>(&self, path: &str) -> (String, CircuitBreaker<P, Failure<B>>)
+    where
+        B: 'static,
+    {
+        let path = path.trim_start_matches('/');
+        let key = match self.layer.scope {
+            GrpcBreakerScope::Service => path.split_once('/').map_or(path, |(service, _)| service),
+            GrpcBreakerScope::Method => path,
+        };
+        let breaker = self.layer.registry.get_or_insert_with(key, || {
+            CircuitBreaker::from_inner((self.layer.factory)(key))
+        });
+        (key.to_string(), breaker)
+    }
+}
+
+impl<S, P> Clone for GrpcBreakerService<S, P>
+where
+    S: Clone,
+    P: BreakerPolicy,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+/// Gets the status code of a response, if it has one in its headers.
+fn status_code<B>(response: &Response<B>) -> Option<Code> {
+    let status = response.headers().get(Status::GRPC_STATUS)?;
+    Some(Code::from_bytes(status.as_bytes()))
+}
+
+impl<S, P, ReqBody, ResBody> Service<Request<ReqBody>> for GrpcBreakerService<S, P>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    P: BreakerPolicy,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Response<ResBody>, BoxError>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let (key, breaker) = self.breaker(req.uri().path());
+        let failure_codes = Arc::clone(&self.layer.failure_codes);
+        let ignored_codes = Arc::clone(&self.layer.ignored_codes);
+        // The ready service is moved into the future, and only called once the
+        // breaker admitted the request
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let result = breaker
+                .call_async_ignoring(
+                    || async move {
+                        match inner.call(req).await {
+                            Ok(response) => match status_code(&response) {
+                                Some(code) if failure_codes.contains(&code) => {
+                                    Err(Failure(Ok(response)))
+                                }
+                                _ => Ok(response),
+                            },
+                            Err(e) => Err(Failure(Err(e.into()))),
+                        }
+                    },
+                    |result| {
+                        matches!(result, Ok(response)
+                            if status_code(response).is_some_and(|code| ignored_codes.contains(&code)))
+                    },
+                )
+                .await;
+
+            let status = match result {
+                Ok(response) | Err(BreakerError::Operation(Failure(Ok(response)))) => {
+                    return Ok(response)
+                }
+                Err(BreakerError::Operation(Failure(Err(e)))) => return Err(e),
+                Err(BreakerError::RateLimited) => Status::resource_exhausted(format!(
+                    "Circuit breaker rate limit exceeded for {}",
+                    key
+                )),
+                Err(BreakerError::Internal(e)) => {
+                    Status::internal(format!("Circuit breaker internal error for {}: {}", key, e))
+                }
+                Err(e) => Status::unavailable(format!("{} for {}", e, key)),
+            };
+            Err(status.into())
+        })
+    }
+}
@@ -0,0 +1,134 @@
+//! Hedged requests for cutting tail latency.
+
+use std::time::{Duration, Instant};
+
+use crate::error::ConfigError;
+use crate::metrics::LatencyWindow;
+
+// Latencies observed before the percentile is trusted over the initial delay
+const MIN_SAMPLES: u64 = 20;
+
+/// When [`CircuitBreaker::call_async_hedged`] launches a second attempt.
+///
+/// The delay is either fixed, or follows a percentile of the latencies of
+/// attempts over the last minute, e.g. the 95th percentile so that about one
+/// call in twenty is hedged. Failed attempts count too, so that fast failures do
+/// not leave the delay tuned to slow successes, and so do cancelled attempts with
+/// the time they ran for, so that the delay does not learn only from the faster
+/// of each pair of attempts.
+///
+/// [`CircuitBreaker::call_async_hedged`]: crate::CircuitBreaker::call_async_hedged
+pub struct Hedge {
+    delay: HedgeDelay,
+}
+
+enum HedgeDelay {
+    Fixed(Duration),
+    Percentile {
+        quantile: f64,
+        initial: Duration,
+        window: LatencyWindow,
+    },
+}
+
+impl Hedge {
+    /// Hedges calls that take longer than `delay`.
+    pub fn fixed(delay: Duration) -> Self {
+        Self {
+            delay: HedgeDelay::Fixed(delay),
+        }
+    }
+
+    /// Hedges calls that take longer than the given percentile of observed
+    /// latency, e.g. 0.95, using `initial_delay` until 20 calls were observed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `quantile` is not in (0.0, 1.0]. Use [`Hedge::try_percentile`]
+    /// to handle invalid values.
+    pub fn percentile(quantile: f64, initial_delay: Duration) -> Self {
+        Self::try_percentile(quantile, initial_delay).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Hedges calls that take longer than the given percentile of observed
+    /// latency, or returns every invalid setting.
+    pub fn try_percentile(quantile: f64, initial_delay: Duration) -> Result<Self, ConfigError> {
+        let mut errors = ConfigError::new();
+        if !(quantile > 0.0 && quantile <= 1.0) {
+            errors.push(
+                "percentile",
+                format!("must be in (0.0, 1.0], got {}", quantile),
+            );
+        }
+        errors.into_result()?;
+
+        Ok(Self {
+            delay: HedgeDelay::Percentile {
+                quantile,
+                initial: initial_delay,
+                window: LatencyWindow::new(Duration::from_secs(60), 10),
+            },
+        })
+    }
+
+    /// Gets the time after which the next call will be hedged.
+    pub fn delay(&self) -> Duration {
+        match &self.delay {
+            HedgeDelay::Fixed(delay) => *delay,
+            HedgeDelay::Percentile {
+                quantile,
+                initial,
+                window,
+            } => {
+                if window.count() < MIN_SAMPLES {
+                    return *initial;
+                }
+                window.percentile(*quantile).unwrap_or(*initial)
+            }
+        }
+    }
+
+    /// Records the latency of an attempt.
+    fn record(&self, latency: Duration) {
+        if let HedgeDelay::Percentile { window, .. } = &self.delay {
+            window.record(latency);
+        }
+    }
+
+    /// Starts timing an attempt.
+    pub(crate) fn start_attempt(&self) -> AttemptTimer<'_> {
+        AttemptTimer {
+            hedge: self,
+            start: Instant::now(),
+            finished: false,
+        }
+    }
+}
+
+/// Times an attempt of a hedged call, recording how long it ran for if it is
+/// dropped before completing.
+pub(crate) struct AttemptTimer<'a> {
+    hedge: &'a Hedge,
+    start: Instant,
+    finished: bool,
+}
+
+impl AttemptTimer<'_> {
+    /// Records the latency of the completed attempt, unless it never `ran`
+    /// because the breaker rejected it.
+    pub(crate) fn finish(mut self, ran: bool) {
+        self.finished = true;
+        if ran {
+            self.hedge.record(self.start.elapsed());
+        }
+    }
+}
+
+impl Drop for AttemptTimer<'_> {
+    fn drop(&mut self) {
+        // A cancelled attempt took at least as long as it ran for
+        if !self.finished {
+            self.hedge.record(self.start.elapsed());
+        }
+    }
+}
@@ -1,10 +1,12 @@
 //! Hook registry for circuit breaker events.
 
+use crate::config::RuntimeConfig;
 use crate::state::State;
 use parking_lot::RwLock;
 use std::sync::Arc;
 
 type HookFn = Arc<dyn Fn() + Send + Sync + 'static>;
+type ConfigHookFn = Arc<dyn Fn(&RuntimeConfig, &RuntimeConfig) + Send + Sync + 'static>;
 
 /// A registry for circuit breaker event hooks.
 pub struct HookRegistry {
@@ -13,6 +15,7 @@ pub struct HookRegistry {
     on_half_open: RwLock<Option<HookFn>>,
     on_success: RwLock<Option<HookFn>>,
     on_failure: RwLock<Option<HookFn>>,
+    on_config_change: RwLock<Option<ConfigHookFn>>,
 }
 
 impl Default for HookRegistry {
@@ -30,6 +33,7 @@ impl HookRegistry {
             on_half_open: RwLock::new(None),
             on_success: RwLock::new(None),
             on_failure: RwLock::new(None),
+            on_config_change: RwLock::new(None),
         }
     }
 
@@ -73,6 +77,16 @@ impl HookRegistry {
         *self.on_failure.write() = Some(Arc::new(f));
     }
 
+    /// Sets the hook to call when the breaker's runtime configuration changes.
+    ///
+    /// The hook receives the old and the new configuration.
+    pub fn set_on_config_change<F>(&self, f: F)
+    where
+        F: Fn(&RuntimeConfig, &RuntimeConfig) + Send + Sync + 'static,
+    {
+        *self.on_config_change.write() = Some(Arc::new(f));
+    }
+
     /// Executes the appropriate hook for a state transition.
     pub fn execute_state_transition_hook(&self, to: State) {
         match to {
@@ -107,6 +121,13 @@ impl HookRegistry {
             hook();
         }
     }
+
+    /// Executes the config change hook.
+    pub fn execute_config_change_hook(&self, old: &RuntimeConfig, new: &RuntimeConfig) {
+        if let Some(hook) = self.on_config_change.read().as_ref() {
+            hook(old, new);
+        }
+    }
 }
 
 #[cfg(feature = "async")]
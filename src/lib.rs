@@ -76,25 +76,83 @@
 //! - `async` - Async support with Tokio
 //! - `prometheus` - Prometheus metrics integration
 //! - `tracing` - Tracing integration
+//! - `serde` - Loading breaker configuration from files and the environment
+//! - `tower` - Tower `Layer` and `Service` middleware
+//! - `http` - Tower middleware for `http` services shedding load while a circuit is open
+//! - `axum` - Axum responses for breaker errors, on top of `http`
+//! - `reqwest` - `reqwest-middleware` middleware with a breaker per host
+//! - `tonic` - Tonic gRPC client layer with a breaker per service or method
+//! - `macros` - The `#[circuit_breaker]` attribute for wrapping functions
 
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 mod breaker;
+mod bulkhead;
+mod combinator;
 mod config;
 mod error;
+#[cfg(feature = "tonic")]
+mod grpc;
+#[cfg(feature = "async")]
+mod hedge;
 mod hook;
+mod limiter;
+#[cfg(feature = "http")]
+mod load_shed;
 mod metrics;
+#[cfg(feature = "reqwest")]
+mod middleware;
+mod pipeline;
 mod policy;
 pub mod prelude;
+mod random;
+mod rate_limit;
+mod registry;
+mod retry;
+#[cfg(feature = "tower")]
+mod service;
+#[cfg(feature = "serde")]
+mod settings;
 mod state;
+mod time;
 
 // Re-exports
 pub use breaker::CircuitBreaker;
-pub use config::BreakerBuilder;
-pub use error::{BreakerError, BreakerResult};
+#[cfg(feature = "macros")]
+pub use circuitbreaker_rs_macros::circuit_breaker;
+pub use combinator::{AllOf, AnyOf, Not, PolicyExt};
+pub use config::{BreakerBuilder, NoPolicy, RuntimeConfig, WithPolicy};
+pub use error::{
+    BreakerError, BreakerResult, ConfigError, InvalidField, PipelineError, PipelineResult,
+};
+#[cfg(feature = "tonic")]
+pub use grpc::{GrpcBreakerLayer, GrpcBreakerScope, GrpcBreakerService};
+#[cfg(feature = "async")]
+pub use hedge::Hedge;
 pub use hook::HookRegistry;
-pub use metrics::{EMAWindow, FixedWindow, MetricSink};
-pub use policy::{BreakerPolicy, DefaultPolicy, ThroughputAwarePolicy, TimeBasedPolicy};
+pub use limiter::{AdaptiveLimiter, AdaptiveLimiterBuilder, LimitAlgorithm};
+#[cfg(feature = "http")]
+pub use load_shed::{BreakerErrorResponse, LoadShedLayer, LoadShedService};
+pub use metrics::{
+    CountWindow, EMAWindow, FixedWindow, LatencyWindow, MetricSink, NullMetricSink, StatsView,
+};
+#[cfg(feature = "reqwest")]
+pub use middleware::{CircuitBreakerMiddleware, RequestRejected};
+pub use pipeline::{Pipeline, PipelineBuilder};
+pub use policy::{
+    AdaptiveThrottlePolicy, AdaptiveThrottlePolicyBuilder, BoxedPolicy, BreakerPolicy,
+    DefaultPolicy, LatencyPolicy, LatencyPolicyBuilder, PolicyThresholds, SloBurnRatePolicy,
+    SloBurnRatePolicyBuilder, ThroughputAwarePolicy, ThroughputAwarePolicyBuilder, TimeBasedPolicy,
+    TimeBasedPolicyBuilder,
+};
+pub use registry::BreakerRegistry;
+pub use retry::{Retry, RetryBudget, RetryBudgetBuilder, RetryBuilder};
+#[cfg(feature = "tower")]
+pub use service::{
+    CircuitBreakerLayer, CircuitBreakerService, ErrorClassifier, ResponseClassifier,
+};
+#[cfg(feature = "serde")]
+pub use settings::{BreakerConfig, BreakerSettings, EnvError, PolicyConfig};
 pub use state::State;
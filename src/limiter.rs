@@ -0,0 +1,286 @@
+//! Adaptive concurrency limiting based on call latency.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::error::ConfigError;
+
+/// Algorithm used by an [`AdaptiveLimiter`] to adjust its limit after each call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitAlgorithm {
+    /// Additive increase, multiplicative decrease.
+    ///
+    /// The limit grows by one after each fast, successful call made while at
+    /// least half of the limit was in use, and shrinks by `backoff_ratio` after
+    /// each failed call or call slower than `latency_threshold`.
+    Aimd {
+        /// Factor the limit is multiplied by on a failed or slow call, in (0.0, 1.0).
+        backoff_ratio: f64,
+        /// Latency above which a call counts as a sign of overload.
+        latency_threshold: Duration,
+    },
+
+    /// Gradient of the fastest observed latency to the current latency.
+    ///
+    /// After each successful call the limit moves towards
+    /// `limit * gradient + sqrt(limit)`, where the gradient is the lowest
+    /// latency seen so far divided by the call's latency, clamped to
+    /// [0.5, 1.0]. The square root leaves room for a small queue, so that the
+    /// limit keeps probing upwards while latency stays at its minimum.
+    Gradient {
+        /// Weight of each new estimate in the limit, in (0.0, 1.0].
+        smoothing: f64,
+    },
+}
+
+impl LimitAlgorithm {
+    /// Records every out-of-range setting in `errors`.
+    fn check(&self, errors: &mut ConfigError) {
+        match *self {
+            LimitAlgorithm::Aimd {
+                backoff_ratio,
+                latency_threshold,
+            } => {
+                if !(backoff_ratio > 0.0 && backoff_ratio < 1.0) {
+                    errors.push(
+                        "backoff_ratio",
+                        format!("must be in (0.0, 1.0), got {}", backoff_ratio),
+                    );
+                }
+                if latency_threshold.is_zero() {
+                    errors.push("latency_threshold", "must be greater than zero");
+                }
+            }
+            LimitAlgorithm::Gradient { smoothing } => {
+                if !(smoothing > 0.0 && smoothing <= 1.0) {
+                    errors.push(
+                        "smoothing",
+                        format!("must be in (0.0, 1.0], got {}", smoothing),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// A limit on concurrent calls that adapts to the latency of those calls.
+///
+/// When attached to a breaker with [`BreakerBuilder::adaptive_limit`], every
+/// call takes a slot before it is attempted and fails with
+/// [`BreakerError::LimitExceeded`] once the limit is reached. The limit is
+/// adjusted from the outcome and duration of each completed call according to
+/// its [`LimitAlgorithm`], and always stays between the configured minimum and
+/// maximum.
+///
+/// [`BreakerBuilder::adaptive_limit`]: crate::BreakerBuilder::adaptive_limit
+/// [`BreakerError::LimitExceeded`]: crate::BreakerError::LimitExceeded
+#[derive(Debug)]
+pub struct AdaptiveLimiter {
+    algorithm: LimitAlgorithm,
+    // f64 bits of the current, fractional limit
+    limit: AtomicU64,
+    min_limit: usize,
+    max_limit: usize,
+    in_flight: AtomicUsize,
+    // Lowest latency seen in nanoseconds, used by the gradient algorithm
+    min_latency: AtomicU64,
+}
+
+impl AdaptiveLimiter {
+    /// Creates a new limiter starting at `initial_limit`, from settings checked by
+    /// [`AdaptiveLimiterBuilder::try_build`].
+    pub(crate) fn new(
+        algorithm: LimitAlgorithm,
+        initial_limit: usize,
+        min_limit: usize,
+        max_limit: usize,
+    ) -> Self {
+        Self {
+            algorithm,
+            limit: AtomicU64::new((initial_limit as f64).to_bits()),
+            min_limit,
+            max_limit,
+            in_flight: AtomicUsize::new(0),
+            min_latency: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    /// Creates a builder for a limiter with default settings.
+    pub fn builder() -> AdaptiveLimiterBuilder {
+        AdaptiveLimiterBuilder::new()
+    }
+
+    /// Gets the current number of calls that may run concurrently.
+    pub fn limit(&self) -> usize {
+        f64::from_bits(self.limit.load(Ordering::Relaxed)) as usize
+    }
+
+    /// Gets the number of calls currently running.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Takes a slot for a call, or returns `false` if the limit is reached.
+    pub(crate) fn try_acquire(&self) -> bool {
+        let limit = self.limit();
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |n| {
+                (n < limit).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    /// Releases a slot taken by [`AdaptiveLimiter::try_acquire`].
+    pub(crate) fn release(&self) {
+        self.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Adjusts the limit from a completed call that still holds its slot.
+    pub(crate) fn record(&self, success: bool, duration: Duration) {
+        let in_flight = self.in_flight() as f64;
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let min_latency = match self.algorithm {
+            LimitAlgorithm::Gradient { .. } if success => self
+                .min_latency
+                .fetch_min(nanos, Ordering::Relaxed)
+                .min(nanos),
+            _ => u64::MAX,
+        };
+
+        let _ = self
+            .limit
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                let limit = f64::from_bits(bits);
+                // Only grow the limit while it is actually being used
+                let saturated = in_flight * 2.0 >= limit;
+                let new = match self.algorithm {
+                    LimitAlgorithm::Aimd {
+                        backoff_ratio,
+                        latency_threshold,
+                    } => {
+                        if !success || duration > latency_threshold {
+                            limit * backoff_ratio
+                        } else if saturated {
+                            limit + 1.0
+                        } else {
+                            return None;
+                        }
+                    }
+                    LimitAlgorithm::Gradient { smoothing } => {
+                        // Failures often return fast and say little about queueing
+                        if !success {
+                            return None;
+                        }
+                        let gradient = min_latency as f64 / nanos.max(1) as f64;
+                        let gradient = gradient.clamp(0.5, 1.0);
+                        let mut estimate = limit * gradient + limit.sqrt();
+                        if !saturated {
+                            estimate = estimate.min(limit);
+                        }
+                        limit * (1.0 - smoothing) + estimate * smoothing
+                    }
+                };
+                let new = new.clamp(self.min_limit as f64, self.max_limit as f64);
+                Some(new.to_bits())
+            });
+    }
+}
+
+/// Builder for [`AdaptiveLimiter`].
+///
+/// Defaults to the AIMD algorithm backing off by 0.9 on failures and calls slower
+/// than one second, starting at 20 concurrent calls within a range of 1 to 200.
+#[derive(Debug, Clone)]
+pub struct AdaptiveLimiterBuilder {
+    algorithm: LimitAlgorithm,
+    initial_limit: usize,
+    min_limit: usize,
+    max_limit: usize,
+}
+
+impl Default for AdaptiveLimiterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveLimiterBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            algorithm: LimitAlgorithm::Aimd {
+                backoff_ratio: 0.9,
+                latency_threshold: Duration::from_secs(1),
+            },
+            initial_limit: 20,
+            min_limit: 1,
+            max_limit: 200,
+        }
+    }
+
+    /// Sets the algorithm that adjusts the limit.
+    pub fn algorithm(mut self, algorithm: LimitAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets the limit used before any call has completed.
+    pub fn initial_limit(mut self, limit: usize) -> Self {
+        self.initial_limit = limit;
+        self
+    }
+
+    /// Sets the lowest value the limit can shrink to.
+    pub fn min_limit(mut self, limit: usize) -> Self {
+        self.min_limit = limit;
+        self
+    }
+
+    /// Sets the highest value the limit can grow to.
+    pub fn max_limit(mut self, limit: usize) -> Self {
+        self.max_limit = limit;
+        self
+    }
+
+    /// Builds the limiter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`AdaptiveLimiterBuilder::try_build`] to handle invalid values.
+    pub fn build(self) -> AdaptiveLimiter {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the limiter, or returns every invalid setting.
+    pub fn try_build(self) -> Result<AdaptiveLimiter, ConfigError> {
+        let mut errors = ConfigError::new();
+        self.algorithm.check(&mut errors);
+        if self.min_limit == 0 {
+            errors.push("min_limit", "must be at least 1");
+        }
+        if self.max_limit < self.min_limit {
+            errors.push(
+                "max_limit",
+                format!("must be at least min_limit ({})", self.min_limit),
+            );
+        }
+        if !(self.min_limit..=self.max_limit).contains(&self.initial_limit) {
+            errors.push(
+                "initial_limit",
+                format!(
+                    "must be between min_limit and max_limit, got {}",
+                    self.initial_limit
+                ),
+            );
+        }
+        errors.into_result()?;
+
+        Ok(AdaptiveLimiter::new(
+            self.algorithm,
+            self.initial_limit,
+            self.min_limit,
+            self.max_limit,
+        ))
+    }
+}
@@ -0,0 +1,250 @@
+//! HTTP middleware failing requests fast while a downstream circuit is open.
+
+use std::convert::Infallible;
+use std::error::Error;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use http::header::RETRY_AFTER;
+use http::{HeaderValue, Request, Response, StatusCode};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::breaker::CircuitBreaker;
+use crate::error::BreakerError;
+use crate::policy::BreakerPolicy;
+use crate::state::State;
+
+/// An HTTP response for the error of a call through a circuit breaker.
+///
+/// Rejections by the breaker map to `503 Service Unavailable`, with a
+/// `Retry-After` header holding the remaining cooldown if the circuit is open,
+/// except rate limiting which maps to `429 Too Many Requests`. Failures of the
+/// operation map to `502 Bad Gateway`, without exposing the downstream error.
+///
+/// Converts into an [`http::Response`] with any body that can be created from
+/// the reason phrase, and with the `axum` feature, implements axum's
+/// `IntoResponse`.
+///
+/// ```rust,ignore
+/// async fn handler(State(breaker): State<Breaker>) -> Result<String, BreakerErrorResponse> {
+///     breaker
+///         .call_async(|| fetch_payment())
+///         .await
+///         .map_err(|e| BreakerErrorResponse::new(&breaker, &e))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerErrorResponse {
+    status: StatusCode,
+    retry_after: Option<Duration>,
+}
+
+impl BreakerErrorResponse {
+    /// Creates the response for `error`, returned by a call through `breaker`.
+    pub fn new<P, E>(breaker: &CircuitBreaker<P, E>, error: &BreakerError<E>) -> Self
+    where
+        P: BreakerPolicy,
+        E: Error + 'static,
+    {
+        let status = match error {
+            BreakerError::Open
+            | BreakerError::Throttled
+            | BreakerError::LimitExceeded
+            | BreakerError::BulkheadFull => StatusCode::SERVICE_UNAVAILABLE,
+            BreakerError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            BreakerError::Operation(_) => StatusCode::BAD_GATEWAY,
+            BreakerError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let retry_after = match error {
+            BreakerError::Open if breaker.current_state() == State::Open => {
+                Some(breaker.remaining_cooldown())
+            }
+            _ => None,
+        };
+
+        Self {
+            status,
+            retry_after,
+        }
+    }
+
+    /// Creates the `503 Service Unavailable` response for a circuit open for
+    /// another `remaining_cooldown`.
+    pub fn open(remaining_cooldown: Duration) -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            retry_after: Some(remaining_cooldown),
+        }
+    }
+
+    /// Gets the status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Gets the time after which the request may be retried, if known.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+}
+
+impl<B> From<BreakerErrorResponse> for Response<B>
+where
+    B: From<&'static str>,
+{
+    fn from(error: BreakerErrorResponse) -> Self {
+        let body = error.status.canonical_reason().unwrap_or_default();
+        let mut response = Response::new(B::from(body));
+        *response.status_mut() = error.status;
+        if let Some(retry_after) = error.retry_after {
+            // Whole seconds, rounded up so that clients do not retry too early
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+        }
+        response
+    }
+}
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for BreakerErrorResponse {
+    fn into_response(self) -> axum::response::Response {
+        let mut response = Response::<axum::body::Body>::from(self);
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+        response
+    }
+}
+
+/// A [`Layer`] failing requests fast with [`BreakerErrorResponse::open`] while
+/// the circuit of a downstream breaker is open.
+///
+/// The layer only reads the breaker's state: outcomes are still recorded by the
+/// calls made through the breaker inside the handlers. Once the cooldown has
+/// elapsed, requests are let through again so that handlers can make the probe
+/// calls.
+///
+/// It works with any service taking an [`http::Request`] and returning an
+/// [`http::Response`], such as hyper services and axum routers.
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/payments", post(create_payment))
+///     .route_layer(LoadShedLayer::new(payments_breaker.clone()));
+/// ```
+pub struct LoadShedLayer<P>
+where
+    P: BreakerPolicy,
+{
+    breaker: CircuitBreaker<P, Infallible>,
+}
+
+impl<P> LoadShedLayer<P>
+where
+    P: BreakerPolicy,
+{
+    /// Creates a layer shedding requests while the circuit of `breaker` is open.
+    pub fn new<E>(breaker: CircuitBreaker<P, E>) -> Self
+    where
+        E: Error + 'static,
+    {
+        Self {
+            breaker: CircuitBreaker::from_inner(Arc::clone(breaker.inner())),
+        }
+    }
+}
+
+impl<P> Clone for LoadShedLayer<P>
+where
+    P: BreakerPolicy,
+{
+    fn clone(&self) -> Self {
+        Self {
+            breaker: self.breaker.clone(),
+        }
+    }
+}
+
+impl<S, P> Layer<S> for LoadShedLayer<P>
+where
+    P: BreakerPolicy,
+{
+    type Service = LoadShedService<S, P>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoadShedService {
+            inner,
+            breaker: self.breaker.clone(),
+            shedding: false,
+        }
+    }
+}
+
+/// A [`Service`] failing requests fast while the circuit of a downstream
+/// breaker is open.
+///
+/// While the circuit is open, [`Service::poll_ready`] is ready without polling
+/// the inner service, so that no capacity of the inner service is reserved for
+/// requests that are shed.
+///
+/// See [`LoadShedLayer`].
+pub struct LoadShedService<S, P>
+where
+    P: BreakerPolicy,
+{
+    inner: S,
+    breaker: CircuitBreaker<P, Infallible>,
+    // Whether the next request is shed, decided when polled for readiness
+    shedding: bool,
+}
+
+impl<S, P> Clone for LoadShedService<S, P>
+where
+    S: Clone,
+    P: BreakerPolicy,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            breaker: self.breaker.clone(),
+            shedding: false,
+        }
+    }
+}
+
+impl<S, P, ReqBody, ResBody> Service<Request<ReqBody>> for LoadShedService<S, P>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    P: BreakerPolicy,
+    ResBody: From<&'static str> + Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<ResBody>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.shedding = !self.breaker.remaining_cooldown().is_zero();
+        if self.shedding {
+            return Poll::Ready(Ok(()));
+        }
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let remaining = self.breaker.remaining_cooldown();
+        // A request is shed if the inner service was not polled for it
+        if std::mem::take(&mut self.shedding) || !remaining.is_zero() {
+            let response = BreakerErrorResponse::open(remaining).into();
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(req))
+    }
+}
@@ -1,8 +1,9 @@
 //! Failure tracking and metrics for circuit breaker.
 
-use parking_lot::Mutex;
-use smallvec::SmallVec;
-use std::sync::atomic::{AtomicU64, Ordering};
+use crate::config::RuntimeConfig;
+use crate::error::ConfigError;
+use crate::time::AtomicInstant;
+use std::sync::atomic::{fence, AtomicI64, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 /// Trait for metrics sinks that can receive circuit breaker events.
@@ -18,6 +19,17 @@ pub trait MetricSink: Send + Sync + 'static {
 
     /// Records a call result.
     fn record_call(&self, success: bool, duration: Duration);
+
+    /// Records a call rejected without being attempted, with the reason such as
+    /// `"open"` or `"throttled"`.
+    fn record_rejection(&self, _reason: &str) {}
+
+    /// Records an attempt to take a retry from a retry budget, with the number of
+    /// retries left in the budget and whether the retry was allowed.
+    fn record_retry_budget(&self, _balance: f64, _withdrawn: bool) {}
+
+    /// Records a change of the breaker's runtime configuration.
+    fn record_config_change(&self, _old: &RuntimeConfig, _new: &RuntimeConfig) {}
 }
 
 /// A null metrics sink that discards all events.
@@ -28,18 +40,85 @@ impl MetricSink for NullMetricSink {
     fn record_error_rate(&self, _rate: f64) {}
     fn record_probe_attempt(&self, _success: bool) {}
     fn record_call(&self, _success: bool, _duration: Duration) {}
+    fn record_rejection(&self, _reason: &str) {}
+    fn record_retry_budget(&self, _balance: f64, _withdrawn: bool) {}
+    fn record_config_change(&self, _old: &RuntimeConfig, _new: &RuntimeConfig) {}
 }
 
 /// Statistics for the circuit breaker.
+///
+/// Call counts are kept in one or more stripes, each on its own cache line.
+/// With several stripes, threads record into different stripes and reads add
+/// them up, so that many cores calling the same breaker do not contend on a
+/// single counter.
+///
+/// Policies are handed a [`StatsView`] taken with [`BreakerStats::snapshot`],
+/// which never mixes values from before and after a reset.
 #[derive(Debug)]
 pub struct BreakerStats {
+    stripes: Box<[StatsStripe]>,
+    consecutive_failures: AtomicU64,
+    // Success count when the last failure was recorded
+    successes_at_last_failure: AtomicU64,
+    // Odd while a reset is in progress, bumped twice by every reset
+    generation: AtomicU64,
+}
+
+/// A consistent snapshot of a breaker's statistics, as seen by policies.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StatsView {
+    /// Number of successful calls since the statistics were last reset.
+    pub success_count: u64,
+
+    /// Number of failed calls since the statistics were last reset.
+    pub failure_count: u64,
+
+    /// Number of failures since the last success.
+    pub consecutive_failures: u64,
+
+    /// Number of successes since the last failure.
+    pub consecutive_successes: u64,
+
+    /// Time of the most recent failure, if any.
+    pub last_failure_time: Option<Instant>,
+}
+
+impl StatsView {
+    /// Gets the total call count.
+    pub fn total_calls(&self) -> u64 {
+        self.success_count + self.failure_count
+    }
+
+    /// Gets the error rate, or zero if no calls were made.
+    pub fn error_rate(&self) -> f64 {
+        let total = self.total_calls();
+        if total == 0 {
+            return 0.0;
+        }
+
+        self.failure_count as f64 / total as f64
+    }
+}
+
+/// The counters recorded by one group of threads.
+#[derive(Debug)]
+#[repr(align(64))]
+struct StatsStripe {
     success_count: AtomicU64,
     failure_count: AtomicU64,
-    consecutive_failures: AtomicU64,
-    consecutive_successes: AtomicU64,
-    last_failure_time: Mutex<Option<Instant>>,
-    last_success_time: Mutex<Option<Instant>>,
-    total_calls: AtomicU64,
+    last_failure_time: AtomicInstant,
+    last_success_time: AtomicInstant,
+}
+
+impl StatsStripe {
+    fn new() -> Self {
+        Self {
+            success_count: AtomicU64::new(0),
+            failure_count: AtomicU64::new(0),
+            last_failure_time: AtomicInstant::none(),
+            last_success_time: AtomicInstant::none(),
+        }
+    }
 }
 
 impl Default for BreakerStats {
@@ -51,61 +130,71 @@ impl Default for BreakerStats {
 impl BreakerStats {
     /// Creates a new empty stats tracker.
     pub fn new() -> Self {
+        Self::with_shards(1)
+    }
+
+    /// Creates a new empty stats tracker whose call counts are striped across
+    /// `shards` cache lines. A value of zero is treated as one.
+    pub fn with_shards(shards: usize) -> Self {
         Self {
-            success_count: AtomicU64::new(0),
-            failure_count: AtomicU64::new(0),
+            stripes: (0..shards.max(1)).map(|_| StatsStripe::new()).collect(),
             consecutive_failures: AtomicU64::new(0),
-            consecutive_successes: AtomicU64::new(0),
-            last_failure_time: Mutex::new(None),
-            last_success_time: Mutex::new(None),
-            total_calls: AtomicU64::new(0),
+            successes_at_last_failure: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
         }
     }
 
     /// Gets the current success count.
     pub fn get_success_count(&self) -> u64 {
-        self.success_count
-            .load(std::sync::atomic::Ordering::Relaxed)
+        self.stripes
+            .iter()
+            .map(|stripe| stripe.success_count.load(Ordering::Relaxed))
+            .sum()
     }
 
     /// Gets the current failure count.
     pub fn get_failure_count(&self) -> u64 {
-        self.failure_count
-            .load(std::sync::atomic::Ordering::Relaxed)
-    }
-
-    /// Gets the total call count.
-    pub fn get_total_calls(&self) -> u64 {
-        self.total_calls.load(std::sync::atomic::Ordering::Relaxed)
+        self.stripes
+            .iter()
+            .map(|stripe| stripe.failure_count.load(Ordering::Relaxed))
+            .sum()
     }
 
     /// Gets the last failure time.
     pub fn get_last_failure_time(&self) -> Option<Instant> {
-        *self.last_failure_time.lock()
+        self.stripes
+            .iter()
+            .filter_map(|stripe| stripe.last_failure_time.get())
+            .max()
     }
 
     /// Records a successful call.
     pub fn record_success(&self) {
-        self.success_count.fetch_add(1, Ordering::Relaxed);
-        self.consecutive_successes.fetch_add(1, Ordering::Relaxed);
-        self.consecutive_failures.store(0, Ordering::Relaxed);
-        self.total_calls.fetch_add(1, Ordering::Relaxed);
-        *self.last_success_time.lock() = Some(Instant::now());
+        let stripe = self.stripe();
+        stripe.success_count.fetch_add(1, Ordering::Relaxed);
+        stripe.last_success_time.set_now();
+
+        // Only write when needed so the shared cache line is not invalidated on every call
+        if self.consecutive_failures.load(Ordering::Relaxed) != 0 {
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+        }
     }
 
     /// Records a failed call.
     pub fn record_failure(&self) {
-        self.failure_count.fetch_add(1, Ordering::Relaxed);
+        let stripe = self.stripe();
+        stripe.failure_count.fetch_add(1, Ordering::Relaxed);
+        stripe.last_failure_time.set_now();
+
         self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
-        self.consecutive_successes.store(0, Ordering::Relaxed);
-        self.total_calls.fetch_add(1, Ordering::Relaxed);
-        *self.last_failure_time.lock() = Some(Instant::now());
+        self.successes_at_last_failure
+            .store(self.get_success_count(), Ordering::Relaxed);
     }
 
     /// Gets the current error rate.
     pub fn error_rate(&self) -> f64 {
-        let failures = self.failure_count.load(Ordering::Relaxed);
-        let total = self.total_calls.load(Ordering::Relaxed);
+        let failures = self.get_failure_count();
+        let total = failures + self.get_success_count();
 
         if total == 0 {
             return 0.0;
@@ -121,83 +210,182 @@ impl BreakerStats {
 
     /// Gets the number of consecutive successes.
     pub fn consecutive_successes(&self) -> u64 {
-        self.consecutive_successes.load(Ordering::Relaxed)
+        self.get_success_count()
+            .saturating_sub(self.successes_at_last_failure.load(Ordering::Relaxed))
+    }
+
+    /// Takes a consistent snapshot of the statistics.
+    ///
+    /// Calls recorded while the snapshot is taken may or may not be included, but
+    /// the snapshot never combines values from before and after a reset.
+    pub fn snapshot(&self) -> StatsView {
+        loop {
+            let generation = self.generation.load(Ordering::Acquire);
+            if generation % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+
+            let success_count = self.get_success_count();
+            let failure_count = self.get_failure_count();
+            let consecutive_failures = self.consecutive_failures();
+            let consecutive_successes = self.consecutive_successes();
+            let last_failure_time = self.get_last_failure_time();
+
+            fence(Ordering::Acquire);
+            if self.generation.load(Ordering::Relaxed) != generation {
+                continue;
+            }
+
+            return StatsView {
+                success_count,
+                failure_count,
+                // A failure racing with a reset can leave its streak counted but not its call
+                consecutive_failures: consecutive_failures.min(failure_count),
+                consecutive_successes: consecutive_successes.min(success_count),
+                last_failure_time,
+            };
+        }
     }
 
     /// Resets all statistics.
     pub fn reset(&self) {
-        self.success_count.store(0, Ordering::Relaxed);
-        self.failure_count.store(0, Ordering::Relaxed);
+        // Mark the reset as in progress, waiting for any concurrent reset to finish
+        let mut generation = self.generation.load(Ordering::Relaxed);
+        loop {
+            if generation % 2 == 1 {
+                std::hint::spin_loop();
+                generation = self.generation.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.generation.compare_exchange_weak(
+                generation,
+                generation + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => generation = actual,
+            }
+        }
+        fence(Ordering::Release);
+
+        for stripe in self.stripes.iter() {
+            stripe.success_count.store(0, Ordering::Relaxed);
+            stripe.failure_count.store(0, Ordering::Relaxed);
+            stripe.last_failure_time.clear();
+            stripe.last_success_time.clear();
+        }
         self.consecutive_failures.store(0, Ordering::Relaxed);
-        self.consecutive_successes.store(0, Ordering::Relaxed);
-        self.total_calls.store(0, Ordering::Relaxed);
-        *self.last_failure_time.lock() = None;
-        *self.last_success_time.lock() = None;
+        self.successes_at_last_failure.store(0, Ordering::Relaxed);
+
+        self.generation.store(generation + 2, Ordering::Release);
+    }
+
+    /// Gets the stripe the current thread records into.
+    fn stripe(&self) -> &StatsStripe {
+        if self.stripes.len() == 1 {
+            return &self.stripes[0];
+        }
+
+        static NEXT_STRIPE: AtomicUsize = AtomicUsize::new(0);
+        thread_local! {
+            static STRIPE: usize = NEXT_STRIPE.fetch_add(1, Ordering::Relaxed);
+        }
+        &self.stripes[STRIPE.with(|stripe| *stripe) % self.stripes.len()]
     }
 }
 
 /// A time window for tracking failures with fixed buckets.
+///
+/// Buckets form a ring indexed by their epoch, the number of bucket lengths since
+/// the window was created. Recording and reading never take a lock: a bucket is
+/// recycled in place once its epoch falls out of the window.
 pub struct FixedWindow {
-    buckets: Mutex<SmallVec<[(Instant, u64, u64); 16]>>, // (timestamp, successes, failures)
-    window_size: Duration,
-    bucket_size: Duration,
+    ring: EpochRing<OutcomeCounts>,
 }
 
 impl FixedWindow {
     /// Creates a new fixed window tracker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is zero or `bucket_count` is zero. Use
+    /// [`FixedWindow::try_new`] to handle invalid values.
     pub fn new(window_size: Duration, bucket_count: usize) -> Self {
-        let bucket_size = window_size / bucket_count as u32;
-        Self {
-            buckets: Mutex::new(SmallVec::new()),
-            window_size,
-            bucket_size,
-        }
+        Self::try_new(window_size, bucket_count).unwrap_or_else(|e| panic!("{}", e))
     }
 
-    /// Records a successful call.
-    pub fn record_success(&self) {
-        let mut buckets = self.buckets.lock();
-        self.clean_old_buckets(&mut buckets);
+    /// Creates a new fixed window tracker, or returns every invalid setting.
+    pub fn try_new(window_size: Duration, bucket_count: usize) -> Result<Self, ConfigError> {
+        let mut errors = ConfigError::new();
+        Self::check(
+            &mut errors,
+            ("window_size", window_size),
+            ("bucket_count", bucket_count),
+        );
+        errors.into_result()?;
+
+        Ok(Self {
+            ring: EpochRing::new(window_size, bucket_count),
+        })
+    }
 
-        let now = Instant::now();
-        if let Some(bucket) = buckets.last_mut() {
-            if now.duration_since(bucket.0) < self.bucket_size {
-                bucket.1 += 1;
-                return;
-            }
+    /// Records every invalid window setting in `errors`, reported under the given
+    /// field names.
+    pub(crate) fn check(
+        errors: &mut ConfigError,
+        (window_field, window_size): (&'static str, Duration),
+        (bucket_field, bucket_count): (&'static str, usize),
+    ) {
+        if window_size.is_zero() {
+            errors.push(window_field, "must be greater than zero");
+        }
+        if bucket_count == 0 || u32::try_from(bucket_count).is_err() {
+            errors.push(
+                bucket_field,
+                format!("must be between 1 and {}, got {}", u32::MAX, bucket_count),
+            );
+        } else if window_size < Duration::from_nanos(bucket_count as u64) {
+            errors.push(
+                bucket_field,
+                format!(
+                    "must not exceed the window size in nanoseconds, got {}",
+                    bucket_count
+                ),
+            );
         }
+    }
 
-        buckets.push((now, 1, 0));
+    /// Records a successful call.
+    pub fn record_success(&self) {
+        self.ring
+            .record(|counts| counts.successes.fetch_add(1, Ordering::Relaxed));
     }
 
     /// Records a failed call.
     pub fn record_failure(&self) {
-        let mut buckets = self.buckets.lock();
-        self.clean_old_buckets(&mut buckets);
-
-        let now = Instant::now();
-        if let Some(bucket) = buckets.last_mut() {
-            if now.duration_since(bucket.0) < self.bucket_size {
-                bucket.2 += 1;
-                return;
-            }
-        }
-
-        buckets.push((now, 0, 1));
+        self.ring
+            .record(|counts| counts.failures.fetch_add(1, Ordering::Relaxed));
     }
 
     /// Gets the current error rate in the window.
     pub fn error_rate(&self) -> f64 {
-        let mut buckets = self.buckets.lock();
-        self.clean_old_buckets(&mut buckets);
-
         let mut total_success = 0;
         let mut total_failure = 0;
 
-        for (_, successes, failures) in buckets.iter() {
-            total_success += successes;
-            total_failure += failures;
-        }
+        self.ring.read(
+            |counts| {
+                (
+                    counts.successes.load(Ordering::Relaxed),
+                    counts.failures.load(Ordering::Relaxed),
+                )
+            },
+            |(successes, failures)| {
+                total_success += successes;
+                total_failure += failures;
+            },
+        );
 
         let total = total_success + total_failure;
         if total == 0 {
@@ -207,18 +395,200 @@ impl FixedWindow {
         total_failure as f64 / total as f64
     }
 
-    fn clean_old_buckets(&self, buckets: &mut SmallVec<[(Instant, u64, u64); 16]>) {
-        let now = Instant::now();
-        let cutoff = now - self.window_size;
+    /// Gets the number of successful calls recorded in the window.
+    pub fn success_count(&self) -> u64 {
+        let mut total = 0;
+        self.ring.read(
+            |counts| counts.successes.load(Ordering::Relaxed),
+            |count| total += count,
+        );
+        total
+    }
+
+    /// Gets the number of calls recorded in the window.
+    pub fn call_count(&self) -> u64 {
+        let mut total = 0;
+        self.ring.read(
+            |counts| {
+                counts.successes.load(Ordering::Relaxed) + counts.failures.load(Ordering::Relaxed)
+            },
+            |count| total += count,
+        );
+        total
+    }
+
+    /// Clears all recorded calls.
+    pub fn reset(&self) {
+        self.ring.reset();
+    }
+}
+
+/// Success and failure counts of one [`FixedWindow`] bucket.
+struct OutcomeCounts {
+    successes: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl RingBucket for OutcomeCounts {
+    fn new() -> Self {
+        Self {
+            successes: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    fn clear(&self) {
+        self.successes.store(0, Ordering::Relaxed);
+        self.failures.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Data kept for one bucket of an [`EpochRing`].
+trait RingBucket {
+    /// Creates an empty bucket.
+    fn new() -> Self;
+
+    /// Empties the bucket before it is reused.
+    fn clear(&self);
+}
+
+// Set on a bucket's epoch while the bucket is being recycled for a newer epoch.
+const EPOCH_ROTATING: u64 = 1 << 63;
+
+/// A lock-free ring of time buckets, each covering one epoch of the window.
+///
+/// The epoch is the number of bucket lengths since the ring was created. A
+/// bucket is recycled in place once its epoch falls out of the window.
+struct EpochRing<T> {
+    slots: Box<[RingSlot<T>]>,
+    origin: Instant,
+    bucket_nanos: u64,
+}
+
+/// One bucket of an [`EpochRing`], padded to its own cache line so that threads
+/// recording into neighbouring buckets do not contend.
+#[repr(align(64))]
+struct RingSlot<T> {
+    // Bucket epoch plus one, so that zero marks an unused bucket
+    epoch: AtomicU64,
+    data: T,
+}
+
+impl<T: RingBucket> EpochRing<T> {
+    /// Creates a ring over a window already validated with [`FixedWindow::check`].
+    fn new(window_size: Duration, bucket_count: usize) -> Self {
+        let bucket_size = window_size / bucket_count as u32;
+        Self {
+            slots: (0..bucket_count)
+                .map(|_| RingSlot {
+                    epoch: AtomicU64::new(0),
+                    data: T::new(),
+                })
+                .collect(),
+            origin: Instant::now(),
+            bucket_nanos: u64::try_from(bucket_size.as_nanos()).unwrap_or(u64::MAX),
+        }
+    }
 
-        while let Some(bucket) = buckets.first() {
-            if bucket.0 < cutoff {
-                buckets.remove(0);
-            } else {
-                break;
+    /// Returns the epoch of the bucket covering the current instant, plus one.
+    fn current_epoch(&self) -> u64 {
+        let elapsed = u64::try_from(self.origin.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        elapsed / self.bucket_nanos + 1
+    }
+
+    /// Runs `record` on the bucket covering the current instant.
+    fn record<R>(&self, record: impl FnOnce(&T) -> R) {
+        let current = self.current_epoch();
+        let slot = &self.slots[(current % self.slots.len() as u64) as usize];
+
+        let mut epoch = slot.epoch.load(Ordering::Acquire);
+        loop {
+            if epoch == current {
+                record(&slot.data);
+                return;
+            }
+
+            if epoch & !EPOCH_ROTATING > current {
+                // Another thread already moved this bucket past our instant
+                return;
+            }
+
+            if epoch & EPOCH_ROTATING != 0 {
+                // Another thread is clearing the bucket and is about to publish it
+                std::hint::spin_loop();
+                epoch = slot.epoch.load(Ordering::Acquire);
+                continue;
+            }
+
+            // The bucket holds an expired epoch: claim it, clear it, then publish
+            // the new epoch so that other threads start recording into it
+            match slot.epoch.compare_exchange_weak(
+                epoch,
+                current | EPOCH_ROTATING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    slot.data.clear();
+                    slot.epoch.store(current, Ordering::Release);
+                    epoch = current;
+                }
+                Err(actual) => epoch = actual,
             }
         }
     }
+
+    /// Reads every bucket within the window with `read`, passing the results of
+    /// buckets that were not recycled while being read to `accept`.
+    fn read<R>(&self, mut read: impl FnMut(&T) -> R, mut accept: impl FnMut(R)) {
+        let current = self.current_epoch();
+        let bucket_count = self.slots.len() as u64;
+
+        for slot in self.slots.iter() {
+            let epoch = slot.epoch.load(Ordering::Acquire);
+            // Skip unused, recycling and expired buckets
+            if epoch == 0
+                || epoch & EPOCH_ROTATING != 0
+                || current.wrapping_sub(epoch) >= bucket_count
+            {
+                continue;
+            }
+
+            let value = read(&slot.data);
+            // The bucket was recycled while we read it, so its data is not ours
+            if slot.epoch.load(Ordering::Acquire) != epoch {
+                continue;
+            }
+
+            accept(value);
+        }
+    }
+
+    /// Empties every bucket.
+    fn reset(&self) {
+        for slot in self.slots.iter() {
+            let mut epoch = slot.epoch.load(Ordering::Acquire);
+            loop {
+                if epoch & EPOCH_ROTATING != 0 {
+                    std::hint::spin_loop();
+                    epoch = slot.epoch.load(Ordering::Acquire);
+                    continue;
+                }
+                match slot.epoch.compare_exchange_weak(
+                    epoch,
+                    EPOCH_ROTATING,
+                    Ordering::Acquire,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => break,
+                    Err(actual) => epoch = actual,
+                }
+            }
+
+            slot.data.clear();
+            slot.epoch.store(0, Ordering::Release);
+        }
+    }
 }
 
 /// A time window for tracking failures with exponential moving average.
@@ -274,4 +644,295 @@ impl EMAWindow {
 
         f64::from_bits(self.error_rate.load(Ordering::Relaxed))
     }
+
+    /// Clears the moving average and the call count.
+    pub fn reset(&self) {
+        self.call_count.store(0, Ordering::Relaxed);
+        self.error_rate.store(0, Ordering::Relaxed);
+    }
+}
+
+const SLOT_EMPTY: u8 = 0;
+const SLOT_SUCCESS: u8 = 1;
+const SLOT_FAILURE: u8 = 2;
+
+/// A sliding window over the outcomes of the last N calls.
+///
+/// Recording and reading are lock-free: each call claims the next slot of a ring
+/// with a single atomic increment, and running totals are adjusted as outcomes
+/// enter and leave the window.
+pub struct CountWindow {
+    slots: Box<[AtomicU8]>,
+    next: AtomicU64,
+    // Signed so that a reset racing with a record can briefly go below zero
+    calls: AtomicI64,
+    failures: AtomicI64,
+}
+
+impl CountWindow {
+    /// Creates a new window holding the outcomes of the last `size` calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero. Use [`CountWindow::try_new`] to handle invalid values.
+    pub fn new(size: usize) -> Self {
+        Self::try_new(size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Creates a new window holding the outcomes of the last `size` calls, or
+    /// returns an error if `size` is zero.
+    pub fn try_new(size: usize) -> Result<Self, ConfigError> {
+        let mut errors = ConfigError::new();
+        Self::check(&mut errors, "size", size);
+        errors.into_result()?;
+
+        Ok(Self {
+            slots: (0..size).map(|_| AtomicU8::new(SLOT_EMPTY)).collect(),
+            next: AtomicU64::new(0),
+            calls: AtomicI64::new(0),
+            failures: AtomicI64::new(0),
+        })
+    }
+
+    /// Records an invalid window size in `errors`, reported under the given field name.
+    pub(crate) fn check(errors: &mut ConfigError, field: &'static str, size: usize) {
+        if size == 0 {
+            errors.push(field, "must be at least 1");
+        }
+    }
+
+    /// Records a successful call.
+    pub fn record_success(&self) {
+        self.record(SLOT_SUCCESS);
+    }
+
+    /// Records a failed call.
+    pub fn record_failure(&self) {
+        self.record(SLOT_FAILURE);
+    }
+
+    fn record(&self, outcome: u8) {
+        // Count the failure before publishing it, so that the call evicting it
+        // always decrements after
+        if outcome == SLOT_FAILURE {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.slots.len() as u64;
+        let evicted = self.slots[index as usize].swap(outcome, Ordering::AcqRel);
+        self.evict(evicted);
+    }
+
+    fn evict(&self, outcome: u8) {
+        match outcome {
+            SLOT_EMPTY => {
+                self.calls.fetch_add(1, Ordering::Relaxed);
+            }
+            SLOT_FAILURE => {
+                self.failures.fetch_sub(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+
+    /// Gets the number of calls currently in the window.
+    pub fn call_count(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed).max(0) as u64
+    }
+
+    /// Gets the number of failed calls currently in the window.
+    pub fn failure_count(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed).max(0) as u64
+    }
+
+    /// Gets the maximum number of calls the window holds.
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Gets the error rate over the calls in the window.
+    pub fn error_rate(&self) -> f64 {
+        let calls = self.call_count();
+        if calls == 0 {
+            return 0.0;
+        }
+
+        // Concurrent updates may briefly count a failure before its call
+        (self.failure_count().min(calls)) as f64 / calls as f64
+    }
+
+    /// Clears all recorded calls.
+    pub fn reset(&self) {
+        for slot in self.slots.iter() {
+            match slot.swap(SLOT_EMPTY, Ordering::AcqRel) {
+                SLOT_EMPTY => {}
+                evicted => {
+                    self.calls.fetch_sub(1, Ordering::Relaxed);
+                    if evicted == SLOT_FAILURE {
+                        self.failures.fetch_sub(1, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Latency histogram buckets are log-linear, as in HDR histograms: values below
+// 2^LATENCY_SUB_BUCKET_BITS microseconds get a bucket each, and every larger
+// power of two is split into 2^(LATENCY_SUB_BUCKET_BITS - 1) equal buckets, which
+// bounds the relative error to 1/8.
+const LATENCY_SUB_BUCKET_BITS: u32 = 4;
+const LATENCY_HALF_BUCKET_COUNT: usize = 1 << (LATENCY_SUB_BUCKET_BITS - 1);
+// Latencies are capped at 2^40 microseconds, roughly 12 days
+const LATENCY_MAX_BITS: u32 = 40;
+const LATENCY_BUCKET_COUNT: usize =
+    (LATENCY_MAX_BITS - LATENCY_SUB_BUCKET_BITS + 2) as usize * LATENCY_HALF_BUCKET_COUNT;
+
+/// A time window of call latencies, kept as a histogram with bounded relative error.
+///
+/// Like [`FixedWindow`], the window is a lock-free ring of time buckets, each of
+/// which holds a histogram of the latencies recorded during its epoch.
+/// Percentiles are reported as the highest latency of the histogram bucket they
+/// fall in, which is at most 12.5% above the true value.
+pub struct LatencyWindow {
+    ring: EpochRing<LatencyCounts>,
+}
+
+impl LatencyWindow {
+    /// Creates a new latency window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is zero or `bucket_count` is zero. Use
+    /// [`LatencyWindow::try_new`] to handle invalid values.
+    pub fn new(window_size: Duration, bucket_count: usize) -> Self {
+        Self::try_new(window_size, bucket_count).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Creates a new latency window, or returns every invalid setting.
+    pub fn try_new(window_size: Duration, bucket_count: usize) -> Result<Self, ConfigError> {
+        let mut errors = ConfigError::new();
+        FixedWindow::check(
+            &mut errors,
+            ("window_size", window_size),
+            ("bucket_count", bucket_count),
+        );
+        errors.into_result()?;
+
+        Ok(Self {
+            ring: EpochRing::new(window_size, bucket_count),
+        })
+    }
+
+    /// Records the latency of a call.
+    pub fn record(&self, latency: Duration) {
+        let index = latency_index(latency);
+        self.ring.record(|counts| {
+            counts.buckets[index].fetch_add(1, Ordering::Relaxed);
+            counts.total.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    /// Gets the number of latencies recorded in the window.
+    pub fn count(&self) -> u64 {
+        let mut total = 0;
+        self.ring.read(
+            |counts| counts.total.load(Ordering::Relaxed),
+            |count| total += count,
+        );
+        total
+    }
+
+    /// Gets the latency below which `quantile` of the calls in the window fall,
+    /// or `None` if the window is empty.
+    ///
+    /// `quantile` is a fraction, e.g. 0.99 for the 99th percentile, and is
+    /// clamped to between 0 and 1.
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        let mut merged = [0u64; LATENCY_BUCKET_COUNT];
+        self.ring.read(
+            |counts| {
+                let mut buckets = [0u64; LATENCY_BUCKET_COUNT];
+                for (bucket, count) in buckets.iter_mut().zip(counts.buckets.iter()) {
+                    *bucket = count.load(Ordering::Relaxed);
+                }
+                buckets
+            },
+            |buckets| {
+                for (merged, count) in merged.iter_mut().zip(buckets) {
+                    *merged += count;
+                }
+            },
+        );
+
+        let total: u64 = merged.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        // The rank of the sample at the quantile, counting from one
+        let rank = ((quantile.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        let index = merged
+            .iter()
+            .position(|count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(LATENCY_BUCKET_COUNT - 1);
+
+        Some(Duration::from_micros(latency_upper_bound(index)))
+    }
+
+    /// Clears all recorded latencies.
+    pub fn reset(&self) {
+        self.ring.reset();
+    }
+}
+
+/// The latency histogram of one [`LatencyWindow`] bucket.
+struct LatencyCounts {
+    buckets: [AtomicU64; LATENCY_BUCKET_COUNT],
+    total: AtomicU64,
+}
+
+impl RingBucket for LatencyCounts {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            total: AtomicU64::new(0),
+        }
+    }
+
+    fn clear(&self) {
+        for bucket in self.buckets.iter() {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.total.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Gets the histogram bucket holding a latency.
+fn latency_index(latency: Duration) -> usize {
+    let micros = u64::try_from(latency.as_micros())
+        .unwrap_or(u64::MAX)
+        .min((1 << LATENCY_MAX_BITS) - 1);
+    if micros < 1 << LATENCY_SUB_BUCKET_BITS {
+        return micros as usize;
+    }
+
+    let magnitude = 63 - micros.leading_zeros() - (LATENCY_SUB_BUCKET_BITS - 1);
+    let mantissa = (micros >> magnitude) as usize;
+    magnitude as usize * LATENCY_HALF_BUCKET_COUNT + mantissa
+}
+
+/// Gets the highest latency in microseconds that falls in a histogram bucket.
+fn latency_upper_bound(index: usize) -> u64 {
+    if index < 1 << LATENCY_SUB_BUCKET_BITS {
+        return index as u64;
+    }
+
+    let magnitude = index / LATENCY_HALF_BUCKET_COUNT - 1;
+    let mantissa = (index - magnitude * LATENCY_HALF_BUCKET_COUNT) as u64;
+    ((mantissa + 1) << magnitude) - 1
 }
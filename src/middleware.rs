@@ -0,0 +1,186 @@
+//! Middleware for `reqwest-middleware` clients, with a breaker per host.
+
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::Arc;
+
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+
+use crate::breaker::{BreakerInner, CircuitBreaker};
+use crate::error::BreakerError;
+use crate::policy::{BoxedPolicy, BreakerPolicy};
+use crate::registry::BreakerRegistry;
+
+type BreakerFactory<P> = Box<dyn Fn(&str) -> Arc<BreakerInner<P>> + Send + Sync>;
+
+/// A failed request, carried through the breaker to be handed back to the
+/// caller unchanged.
+struct Failure(reqwest_middleware::Result<Response>);
+
+impl Debug for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Failure")
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Request failed")
+    }
+}
+
+impl Error for Failure {}
+
+/// Error returned by [`CircuitBreakerMiddleware`] for a request that the
+/// breaker of its host rejected without sending it.
+///
+/// The middleware returns it wrapped in [`reqwest_middleware::Error::Middleware`];
+/// use [`RequestRejected::from_error`] to tell it apart from other errors.
+#[derive(Debug)]
+pub struct RequestRejected {
+    host: String,
+    error: BreakerError<Infallible>,
+}
+
+impl RequestRejected {
+    /// Gets the rejection from an error returned by a client, if the request was
+    /// rejected by a breaker.
+    pub fn from_error(error: &reqwest_middleware::Error) -> Option<&Self> {
+        match error {
+            reqwest_middleware::Error::Middleware(e) => e.downcast_ref(),
+            reqwest_middleware::Error::Reqwest(_) => None,
+        }
+    }
+
+    /// Gets the host whose breaker rejected the request, as `host:port`.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Gets the reason the breaker rejected the request.
+    pub fn error(&self) -> &BreakerError<Infallible> {
+        &self.error
+    }
+
+    /// Whether the request was rejected because the circuit is open.
+    pub fn is_open(&self) -> bool {
+        matches!(self.error, BreakerError::Open)
+    }
+}
+
+impl Display for RequestRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Request to {} rejected: {}", self.host, self.error)
+    }
+}
+
+impl Error for RequestRejected {}
+
+/// A [`Middleware`] sending each request through the circuit breaker of its host.
+///
+/// Breakers are kept in a [`BreakerRegistry`] keyed by `host:port`, and created
+/// on the first request to a host. `5xx` responses, timeouts and connection
+/// errors are recorded as failures, while other responses, including `4xx`, are
+/// recorded as successes. Responses are returned to the caller either way.
+/// Requests rejected by a breaker fail with a [`RequestRejected`] error.
+///
+/// ```rust,ignore
+/// let registry = Arc::new(BreakerRegistry::new());
+/// let client = ClientBuilder::new(reqwest::Client::new())
+///     .with(CircuitBreakerMiddleware::new(Arc::clone(&registry), |_host| {
+///         CircuitBreaker::<BoxedPolicy, reqwest_middleware::Error>::builder()
+///             .consecutive_failures(5)
+///             .boxed()
+///             .build_with_policy()
+///     }))
+///     .build();
+/// ```
+pub struct CircuitBreakerMiddleware<P = BoxedPolicy>
+where
+    P: BreakerPolicy,
+{
+    registry: Arc<BreakerRegistry<P>>,
+    factory: BreakerFactory<P>,
+}
+
+impl<P> CircuitBreakerMiddleware<P>
+where
+    P: BreakerPolicy,
+{
+    /// Creates a middleware looking up breakers in `registry`, creating missing
+    /// ones with `factory`, which is given the `host:port` key.
+    pub fn new<E, F>(registry: Arc<BreakerRegistry<P>>, factory: F) -> Self
+    where
+        E: Error + 'static,
+        F: Fn(&str) -> CircuitBreaker<P, E> + Send + Sync + 'static,
+    {
+        Self {
+            registry,
+            factory: Box::new(move |host| Arc::clone(factory(host).inner())),
+        }
+    }
+
+    /// Gets the registry holding the breakers of every host.
+    pub fn registry(&self) -> &Arc<BreakerRegistry<P>> {
+        &self.registry
+    }
+
+    fn breaker(&self, host: &str) -> CircuitBreaker<P, Failure> {
+        self.registry
+            .get_or_insert_with(host, || CircuitBreaker::from_inner((self.factory)(host)))
+    }
+}
+
+#[async_trait::async_trait]
+impl<P> Middleware for CircuitBreakerMiddleware<P>
+where
+    P: BreakerPolicy,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let url = req.url();
+        let host = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+        let breaker = self.breaker(&host);
+
+        let result = breaker
+            .call_async(|| async {
+                let result = next.run(req, extensions).await;
+                let failed = match &result {
+                    Ok(response) => response.status().is_server_error(),
+                    Err(reqwest_middleware::Error::Reqwest(e)) => e.is_timeout() || e.is_connect(),
+                    Err(reqwest_middleware::Error::Middleware(_)) => false,
+                };
+                if failed {
+                    Err(Failure(result))
+                } else {
+                    Ok(result)
+                }
+            })
+            .await;
+
+        let error = match result {
+            Ok(result) | Err(BreakerError::Operation(Failure(result))) => return result,
+            Err(BreakerError::Open) => BreakerError::Open,
+            Err(BreakerError::Throttled) => BreakerError::Throttled,
+            Err(BreakerError::LimitExceeded) => BreakerError::LimitExceeded,
+            Err(BreakerError::BulkheadFull) => BreakerError::BulkheadFull,
+            Err(BreakerError::RateLimited) => BreakerError::RateLimited,
+            Err(BreakerError::Internal(e)) => BreakerError::Internal(e),
+        };
+        Err(reqwest_middleware::Error::middleware(RequestRejected {
+            host,
+            error,
+        }))
+    }
+}
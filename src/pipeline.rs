@@ -0,0 +1,357 @@
+//! Composing a circuit breaker with timeouts, retries, a bulkhead and a fallback.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::breaker::CircuitBreaker;
+use crate::bulkhead::Bulkhead;
+use crate::error::{BreakerError, ConfigError, PipelineError, PipelineResult};
+use crate::policy::BreakerPolicy;
+use crate::retry::Retry;
+
+type Fallback<E, T> = Box<dyn Fn(&PipelineError<E>) -> Option<T> + Send + Sync + 'static>;
+
+/// Outcome of a single attempt as seen by the breaker, so that timeouts are
+/// recorded as failures.
+#[derive(Debug)]
+enum AttemptError<E> {
+    Timeout,
+    Operation(E),
+}
+
+impl<E: Display> Display for AttemptError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AttemptError::Timeout => write!(f, "Operation timed out"),
+            AttemptError::Operation(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for AttemptError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AttemptError::Timeout => None,
+            AttemptError::Operation(e) => Some(e),
+        }
+    }
+}
+
+impl<E> From<BreakerError<AttemptError<E>>> for PipelineError<E> {
+    fn from(error: BreakerError<AttemptError<E>>) -> Self {
+        match error {
+            BreakerError::Operation(AttemptError::Timeout) => PipelineError::Timeout,
+            BreakerError::Operation(AttemptError::Operation(e)) => PipelineError::Operation(e),
+            BreakerError::Open => PipelineError::Open,
+            BreakerError::Throttled => PipelineError::Throttled,
+            BreakerError::LimitExceeded => PipelineError::LimitExceeded,
+            BreakerError::BulkheadFull => PipelineError::BulkheadFull,
+            BreakerError::RateLimited => PipelineError::RateLimited,
+            BreakerError::Internal(e) => PipelineError::Internal(e),
+        }
+    }
+}
+
+/// A circuit breaker wrapped in further resilience layers, applied in a fixed order.
+///
+/// From the outside in, a call goes through:
+///
+/// 1. the fallback, which may replace any error with a value,
+/// 2. the retry, which repeats the layers below for each attempt,
+/// 3. the bulkhead, which bounds the attempts running at the same time,
+/// 4. the circuit breaker, which records every attempt as its own outcome,
+/// 5. the timeout, which turns slow attempts into failures.
+///
+/// Every layer but the breaker is optional. Errors of all layers are reported
+/// as one [`PipelineError`].
+///
+/// ```rust
+/// use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy, Pipeline, Retry};
+/// use std::time::Duration;
+///
+/// let breaker = CircuitBreaker::<DefaultPolicy, std::io::Error>::builder().build();
+/// let pipeline = Pipeline::builder(breaker)
+///     .timeout(Duration::from_secs(2))
+///     .retry(Retry::builder().max_attempts(3).build())
+///     .max_concurrent_calls(32)
+///     .fallback(|_| Some("cached"))
+///     .build();
+///
+/// let result = pipeline.call(|| Ok::<_, std::io::Error>("fresh"));
+/// assert_eq!(result.unwrap(), "fresh");
+/// ```
+pub struct Pipeline<P, E, T>
+where
+    P: BreakerPolicy,
+    E: Error + 'static,
+{
+    breaker: CircuitBreaker<P, AttemptError<E>>,
+    timeout: Option<Duration>,
+    retry: Option<Retry<E>>,
+    bulkhead: Option<Bulkhead>,
+    fallback: Option<Fallback<E, T>>,
+}
+
+impl<P, E, T> Pipeline<P, E, T>
+where
+    P: BreakerPolicy,
+    E: Error + 'static,
+{
+    /// Creates a builder for a pipeline around `breaker`.
+    pub fn builder(breaker: CircuitBreaker<P, E>) -> PipelineBuilder<P, E, T> {
+        PipelineBuilder::new(breaker)
+    }
+
+    /// Executes a function through every layer of the pipeline.
+    ///
+    /// `f` is called once per attempt. A sync function cannot be interrupted, so
+    /// an attempt that runs past the timeout is completed, and its result then
+    /// discarded as [`PipelineError::Timeout`].
+    pub fn call<F>(&self, mut f: F) -> PipelineResult<T, E>
+    where
+        F: FnMut() -> Result<T, E>,
+    {
+        let mut attempt = 1;
+        let result = loop {
+            let result = self.attempt(&mut f);
+            match &result {
+                Err(error) if self.should_retry(attempt, error) => {
+                    if let Some(retry) = &self.retry {
+                        std::thread::sleep(retry.backoff(attempt));
+                    }
+                    attempt += 1;
+                }
+                _ => break result,
+            }
+        };
+
+        self.apply_fallback(result)
+    }
+
+    /// Makes a single attempt through the bulkhead, breaker and timeout.
+    fn attempt<F>(&self, f: &mut F) -> PipelineResult<T, E>
+    where
+        F: FnMut() -> Result<T, E>,
+    {
+        let _slot = match &self.bulkhead {
+            Some(bulkhead) => Some(bulkhead.enter().ok_or_else(|| self.bulkhead_full())?),
+            None => None,
+        };
+
+        self.breaker
+            .call(|| {
+                let start = Instant::now();
+                let result = f();
+                if self
+                    .timeout
+                    .is_some_and(|timeout| start.elapsed() > timeout)
+                {
+                    return Err(AttemptError::Timeout);
+                }
+                result.map_err(AttemptError::Operation)
+            })
+            .map_err(PipelineError::from)
+    }
+
+    /// Reports an attempt rejected by the pipeline's bulkhead to the breaker's
+    /// metric sink, like rejections by the breaker's own bulkhead.
+    fn bulkhead_full(&self) -> PipelineError<E> {
+        self.breaker.record_rejection("bulkhead_full");
+        PipelineError::BulkheadFull
+    }
+
+    /// Whether a failed attempt is retried, taking a retry from the breaker's
+    /// retry budget if so.
+    fn should_retry(&self, attempt: u32, error: &PipelineError<E>) -> bool {
+        let Some(retry) = &self.retry else {
+            return false;
+        };
+
+        let retryable = match error {
+            PipelineError::Operation(e) => retry.should_retry(attempt, e),
+            PipelineError::Timeout => attempt < retry.max_attempts(),
+            // Rejections end the retries
+            _ => false,
+        };
+        retryable && self.breaker.withdraw_retry()
+    }
+
+    fn apply_fallback(&self, result: PipelineResult<T, E>) -> PipelineResult<T, E> {
+        match (result, &self.fallback) {
+            (Err(error), Some(fallback)) => fallback(&error).ok_or(error),
+            (result, _) => result,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P, E, T> Pipeline<P, E, T>
+where
+    P: BreakerPolicy,
+    E: Error + 'static,
+{
+    /// Executes an async function through every layer of the pipeline.
+    ///
+    /// `f` is called once per attempt. An attempt that runs past the timeout is
+    /// cancelled. Waiting uses the Tokio timer.
+    pub async fn call_async<F, Fut>(&self, mut f: F) -> PipelineResult<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+        let result = loop {
+            let result = self.attempt_async(&mut f).await;
+            match &result {
+                Err(error) if self.should_retry(attempt, error) => {
+                    if let Some(retry) = &self.retry {
+                        tokio::time::sleep(retry.backoff(attempt)).await;
+                    }
+                    attempt += 1;
+                }
+                _ => break result,
+            }
+        };
+
+        self.apply_fallback(result)
+    }
+
+    /// Makes a single attempt through the bulkhead, breaker and timeout.
+    async fn attempt_async<F, Fut>(&self, f: &mut F) -> PipelineResult<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        let _slot = match &self.bulkhead {
+            Some(bulkhead) => Some(
+                bulkhead
+                    .enter_async()
+                    .await
+                    .ok_or_else(|| self.bulkhead_full())?,
+            ),
+            None => None,
+        };
+
+        self.breaker
+            .call_async(|| async {
+                let result = match self.timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, f())
+                        .await
+                        .map_err(|_| AttemptError::Timeout)?,
+                    None => f().await,
+                };
+                result.map_err(AttemptError::Operation)
+            })
+            .await
+            .map_err(PipelineError::from)
+    }
+}
+
+/// Builder for [`Pipeline`].
+///
+/// Starts with no layers besides the circuit breaker.
+pub struct PipelineBuilder<P, E, T>
+where
+    P: BreakerPolicy,
+    E: Error + 'static,
+{
+    breaker: CircuitBreaker<P, E>,
+    timeout: Option<Duration>,
+    retry: Option<Retry<E>>,
+    max_concurrent_calls: Option<usize>,
+    max_queue_time: Duration,
+    fallback: Option<Fallback<E, T>>,
+}
+
+impl<P, E, T> PipelineBuilder<P, E, T>
+where
+    P: BreakerPolicy,
+    E: Error + 'static,
+{
+    /// Creates a new builder for a pipeline around `breaker`.
+    pub fn new(breaker: CircuitBreaker<P, E>) -> Self {
+        Self {
+            breaker,
+            timeout: None,
+            retry: None,
+            max_concurrent_calls: None,
+            max_queue_time: Duration::ZERO,
+            fallback: None,
+        }
+    }
+
+    /// Fails attempts that take longer than `timeout` with [`PipelineError::Timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retries failed attempts, including timed out ones, according to `retry`.
+    pub fn retry(mut self, retry: Retry<E>) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Bounds the number of attempts running at the same time.
+    ///
+    /// See [`BreakerBuilder::max_concurrent_calls`].
+    ///
+    /// [`BreakerBuilder::max_concurrent_calls`]: crate::BreakerBuilder::max_concurrent_calls
+    pub fn max_concurrent_calls(mut self, calls: usize) -> Self {
+        self.max_concurrent_calls = Some(calls);
+        self
+    }
+
+    /// Sets how long an attempt may wait for a slot when the bulkhead is full.
+    ///
+    /// Defaults to zero. Ignored without [`PipelineBuilder::max_concurrent_calls`].
+    pub fn max_queue_time(mut self, duration: Duration) -> Self {
+        self.max_queue_time = duration;
+        self
+    }
+
+    /// Replaces errors with the value returned by `fallback`, unless it returns
+    /// `None`.
+    pub fn fallback<F>(mut self, fallback: F) -> Self
+    where
+        F: Fn(&PipelineError<E>) -> Option<T> + Send + Sync + 'static,
+    {
+        self.fallback = Some(Box::new(fallback));
+        self
+    }
+
+    /// Builds the pipeline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use [`PipelineBuilder::try_build`]
+    /// to handle invalid values.
+    pub fn build(self) -> Pipeline<P, E, T> {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the pipeline, or returns every invalid setting.
+    pub fn try_build(self) -> Result<Pipeline<P, E, T>, ConfigError> {
+        let mut errors = ConfigError::new();
+        if self.timeout.is_some_and(|timeout| timeout.is_zero()) {
+            errors.push("timeout", "must be greater than zero");
+        }
+        if self.max_concurrent_calls == Some(0) {
+            errors.push("max_concurrent_calls", "must be at least 1");
+        }
+        errors.into_result()?;
+
+        Ok(Pipeline {
+            // Shares the breaker's state, recording timeouts as failures
+            breaker: CircuitBreaker::from_inner(Arc::clone(self.breaker.inner())),
+            timeout: self.timeout,
+            retry: self.retry,
+            bulkhead: self
+                .max_concurrent_calls
+                .map(|calls| Bulkhead::new(calls, self.max_queue_time)),
+            fallback: self.fallback,
+        })
+    }
+}
@@ -1,23 +1,148 @@
 //! Policy engine for circuit breaker trip and reset decisions.
 
-use crate::metrics::{BreakerStats, EMAWindow, FixedWindow};
+use crate::config::{
+    DEFAULT_CONSECUTIVE_SUCCESSES, DEFAULT_FAILURE_THRESHOLD, DEFAULT_MIN_THROUGHPUT,
+};
+use crate::error::ConfigError;
+use crate::metrics::{CountWindow, EMAWindow, FixedWindow, LatencyWindow, StatsView};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 /// A policy that determines when to trip and reset a circuit breaker.
 pub trait BreakerPolicy: Send + Sync + 'static {
-    /// Determines if the circuit should trip open based on current stats.
-    fn should_trip(&self, stats: &BreakerStats) -> bool;
+    /// Determines if the circuit should trip open based on a snapshot of the current stats.
+    fn should_trip(&self, stats: &StatsView) -> bool;
 
-    /// Determines if the circuit should reset to closed based on current stats.
-    fn should_reset(&self, stats: &BreakerStats) -> bool;
+    /// Determines if the circuit should reset to closed based on a snapshot of the current stats.
+    fn should_reset(&self, stats: &StatsView) -> bool;
+
+    /// Observes the outcome of a call, before any trip or reset decision is made.
+    ///
+    /// Policies that keep their own windows of recent calls record them here.
+    fn record_outcome(&self, _success: bool, _duration: Duration) {}
+
+    /// Decides whether to let a call through while the circuit is closed.
+    ///
+    /// Policies that shed load gradually instead of tripping return `false` for
+    /// some calls, which then fail with [`BreakerError::Throttled`] without being
+    /// attempted. Admits every call by default.
+    ///
+    /// [`BreakerError::Throttled`]: crate::BreakerError::Throttled
+    fn admit(&self) -> bool {
+        true
+    }
+
+    /// Whether trip decisions should also be made after successful calls.
+    ///
+    /// By default the breaker only asks whether to trip after a failure. Policies
+    /// that can trip on successful calls, such as slow ones, return `true`.
+    fn trips_on_success(&self) -> bool {
+        false
+    }
+
+    /// Clears any call history kept by the policy.
+    ///
+    /// Called whenever the breaker's statistics are reset, such as when the
+    /// circuit closes.
+    fn clear(&self) {}
+
+    /// Returns the policy's tunable thresholds, if it supports runtime reconfiguration.
+    fn thresholds(&self) -> Option<PolicyThresholds> {
+        None
+    }
+
+    /// Applies new thresholds to a running policy.
+    ///
+    /// Policies that do not support runtime reconfiguration ignore this call.
+    fn update_thresholds(&self, _thresholds: PolicyThresholds) {}
+}
+
+/// A type-erased policy, used when the policy is chosen at runtime.
+pub type BoxedPolicy = Box<dyn BreakerPolicy>;
+
+impl BreakerPolicy for BoxedPolicy {
+    fn should_trip(&self, stats: &StatsView) -> bool {
+        (**self).should_trip(stats)
+    }
+
+    fn should_reset(&self, stats: &StatsView) -> bool {
+        (**self).should_reset(stats)
+    }
+
+    fn record_outcome(&self, success: bool, duration: Duration) {
+        (**self).record_outcome(success, duration)
+    }
+
+    fn admit(&self) -> bool {
+        (**self).admit()
+    }
+
+    fn trips_on_success(&self) -> bool {
+        (**self).trips_on_success()
+    }
+
+    fn clear(&self) {
+        (**self).clear()
+    }
+
+    fn thresholds(&self) -> Option<PolicyThresholds> {
+        (**self).thresholds()
+    }
+
+    fn update_thresholds(&self, thresholds: PolicyThresholds) {
+        (**self).update_thresholds(thresholds)
+    }
+}
+
+/// Thresholds used by [`DefaultPolicy`] that can be changed on a running breaker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolicyThresholds {
+    /// Error rate at or above which the circuit trips.
+    pub failure_threshold: f64,
+
+    /// Minimum number of calls before the error rate is considered.
+    pub min_throughput: u64,
+
+    /// Number of consecutive failures that trips the circuit.
+    pub consecutive_failures: u64,
+
+    /// Number of consecutive successes that resets the circuit.
+    pub consecutive_successes: u64,
+}
+
+impl PolicyThresholds {
+    /// Records every out-of-range threshold in `errors`.
+    pub(crate) fn check(&self, errors: &mut ConfigError) {
+        check_ratio(errors, "failure_threshold", self.failure_threshold);
+        if self.consecutive_failures == 0 {
+            errors.push("consecutive_failures", "must be at least 1");
+        }
+        if self.consecutive_successes == 0 {
+            errors.push("consecutive_successes", "must be at least 1");
+        }
+    }
+}
+
+/// Records `field` as invalid unless `value` is between 0.0 and 1.0.
+pub(crate) fn check_ratio(errors: &mut ConfigError, field: &'static str, value: f64) {
+    if !(0.0..=1.0).contains(&value) {
+        errors.push(field, format!("must be between 0.0 and 1.0, got {}", value));
+    }
 }
 
 /// Default policy implementation based on error rate and consecutive failures.
+///
+/// By default the error rate covers every call since the breaker's statistics
+/// were last reset. With [`DefaultPolicy::with_sliding_window`], it covers only
+/// the most recent calls instead.
 pub struct DefaultPolicy {
-    failure_threshold: f64,
-    min_throughput: u64,
-    consecutive_failures_threshold: u64,
-    consecutive_successes_threshold: u64,
+    // Thresholds are read without locking on every decision; the breaker's
+    // configuration lock keeps writers from interleaving
+    failure_threshold: AtomicU64, // f64 bits
+    min_throughput: AtomicU64,
+    consecutive_failures_threshold: AtomicU64,
+    consecutive_successes_threshold: AtomicU64,
+    window: Option<CountWindow>,
 }
 
 impl DefaultPolicy {
@@ -29,30 +154,84 @@ impl DefaultPolicy {
         consecutive_successes_threshold: u64,
     ) -> Self {
         Self {
-            failure_threshold,
-            min_throughput,
-            consecutive_failures_threshold,
-            consecutive_successes_threshold,
+            failure_threshold: AtomicU64::new(failure_threshold.to_bits()),
+            min_throughput: AtomicU64::new(min_throughput),
+            consecutive_failures_threshold: AtomicU64::new(consecutive_failures_threshold),
+            consecutive_successes_threshold: AtomicU64::new(consecutive_successes_threshold),
+            window: None,
         }
     }
+
+    /// Judges the error rate over the last `size` calls only, so that an early
+    /// burst of failures or successes does not dominate the rate forever.
+    ///
+    /// The minimum throughput then applies to the number of calls in the window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn with_sliding_window(mut self, size: usize) -> Self {
+        self.window = Some(CountWindow::new(size));
+        self
+    }
 }
 
 impl BreakerPolicy for DefaultPolicy {
-    fn should_trip(&self, stats: &BreakerStats) -> bool {
+    fn should_trip(&self, stats: &StatsView) -> bool {
         // Trip if error rate exceeds threshold and we have minimum throughput
-        let error_rate = stats.error_rate();
-        let total_calls = stats.get_total_calls();
+        let (error_rate, total_calls) = match &self.window {
+            Some(window) => (window.error_rate(), window.call_count()),
+            None => (stats.error_rate(), stats.total_calls()),
+        };
 
-        if total_calls >= self.min_throughput && error_rate >= self.failure_threshold {
+        if total_calls >= self.min_throughput.load(Ordering::Relaxed)
+            && error_rate >= f64::from_bits(self.failure_threshold.load(Ordering::Relaxed))
+        {
             return true;
         }
 
         // Or if consecutive failures exceed threshold
-        stats.consecutive_failures() >= self.consecutive_failures_threshold
+        stats.consecutive_failures >= self.consecutive_failures_threshold.load(Ordering::Relaxed)
+    }
+
+    fn should_reset(&self, stats: &StatsView) -> bool {
+        stats.consecutive_successes >= self.consecutive_successes_threshold.load(Ordering::Relaxed)
+    }
+
+    fn record_outcome(&self, success: bool, _duration: Duration) {
+        if let Some(window) = &self.window {
+            if success {
+                window.record_success();
+            } else {
+                window.record_failure();
+            }
+        }
+    }
+
+    fn clear(&self) {
+        if let Some(window) = &self.window {
+            window.reset();
+        }
+    }
+
+    fn thresholds(&self) -> Option<PolicyThresholds> {
+        Some(PolicyThresholds {
+            failure_threshold: f64::from_bits(self.failure_threshold.load(Ordering::Relaxed)),
+            min_throughput: self.min_throughput.load(Ordering::Relaxed),
+            consecutive_failures: self.consecutive_failures_threshold.load(Ordering::Relaxed),
+            consecutive_successes: self.consecutive_successes_threshold.load(Ordering::Relaxed),
+        })
     }
 
-    fn should_reset(&self, stats: &BreakerStats) -> bool {
-        stats.consecutive_successes() >= self.consecutive_successes_threshold
+    fn update_thresholds(&self, thresholds: PolicyThresholds) {
+        self.failure_threshold
+            .store(thresholds.failure_threshold.to_bits(), Ordering::Relaxed);
+        self.min_throughput
+            .store(thresholds.min_throughput, Ordering::Relaxed);
+        self.consecutive_failures_threshold
+            .store(thresholds.consecutive_failures, Ordering::Relaxed);
+        self.consecutive_successes_threshold
+            .store(thresholds.consecutive_successes, Ordering::Relaxed);
     }
 }
 
@@ -84,6 +263,11 @@ impl TimeBasedPolicy {
         }
     }
 
+    /// Creates a builder for a time-based policy with default settings.
+    pub fn builder() -> TimeBasedPolicyBuilder {
+        TimeBasedPolicyBuilder::new()
+    }
+
     /// Records a successful call in the time window.
     pub fn record_success(&self) {
         self.window.record_success();
@@ -96,15 +280,15 @@ impl TimeBasedPolicy {
 }
 
 impl BreakerPolicy for TimeBasedPolicy {
-    fn should_trip(&self, stats: &BreakerStats) -> bool {
+    fn should_trip(&self, stats: &StatsView) -> bool {
         let window_error_rate = self.window.error_rate();
-        let total_calls = stats.get_total_calls();
+        let total_calls = stats.total_calls();
 
         window_error_rate >= self.failure_threshold && total_calls >= self.min_call_count
     }
 
-    fn should_reset(&self, stats: &BreakerStats) -> bool {
-        let last_failure = stats.get_last_failure_time();
+    fn should_reset(&self, stats: &StatsView) -> bool {
+        let last_failure = stats.last_failure_time;
 
         if let Some(time) = last_failure {
             if time.elapsed() < self.min_recovery_time {
@@ -112,7 +296,124 @@ impl BreakerPolicy for TimeBasedPolicy {
             }
         }
 
-        stats.consecutive_successes() >= self.consecutive_successes_threshold
+        stats.consecutive_successes >= self.consecutive_successes_threshold
+    }
+
+    fn record_outcome(&self, success: bool, _duration: Duration) {
+        if success {
+            self.record_success();
+        } else {
+            self.record_failure();
+        }
+    }
+
+    fn clear(&self) {
+        self.window.reset();
+    }
+}
+
+/// Builder for [`TimeBasedPolicy`].
+///
+/// Defaults to a 60 second window split into 10 buckets, a failure threshold of
+/// 0.5 over at least 10 calls, no minimum recovery time, and 3 consecutive
+/// successes to reset.
+#[derive(Debug, Clone)]
+pub struct TimeBasedPolicyBuilder {
+    window: Duration,
+    buckets: usize,
+    failure_threshold: f64,
+    min_calls: u64,
+    min_recovery_time: Duration,
+    consecutive_successes: u64,
+}
+
+impl Default for TimeBasedPolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeBasedPolicyBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            buckets: 10,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            min_calls: DEFAULT_MIN_THROUGHPUT,
+            min_recovery_time: Duration::ZERO,
+            consecutive_successes: DEFAULT_CONSECUTIVE_SUCCESSES,
+        }
+    }
+
+    /// Sets the size of the sliding time window.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the number of buckets the window is divided into.
+    pub fn buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    /// Sets the error rate within the window that trips the circuit.
+    pub fn failure_threshold(mut self, threshold: f64) -> Self {
+        self.failure_threshold = threshold;
+        self
+    }
+
+    /// Sets the minimum number of calls required before considering the error rate.
+    pub fn min_calls(mut self, count: u64) -> Self {
+        self.min_calls = count;
+        self
+    }
+
+    /// Sets the minimum time since the last failure before the circuit may reset.
+    pub fn min_recovery_time(mut self, duration: Duration) -> Self {
+        self.min_recovery_time = duration;
+        self
+    }
+
+    /// Sets the number of consecutive successes required to reset the circuit.
+    pub fn consecutive_successes(mut self, count: u64) -> Self {
+        self.consecutive_successes = count;
+        self
+    }
+
+    /// Builds the policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`TimeBasedPolicyBuilder::try_build`] to handle invalid values.
+    pub fn build(self) -> TimeBasedPolicy {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the policy, or returns every invalid setting.
+    pub fn try_build(self) -> Result<TimeBasedPolicy, ConfigError> {
+        let mut errors = ConfigError::new();
+        FixedWindow::check(
+            &mut errors,
+            ("window", self.window),
+            ("buckets", self.buckets),
+        );
+        check_ratio(&mut errors, "failure_threshold", self.failure_threshold);
+        if self.consecutive_successes == 0 {
+            errors.push("consecutive_successes", "must be at least 1");
+        }
+        errors.into_result()?;
+
+        Ok(TimeBasedPolicy::new(
+            self.window,
+            self.buckets,
+            self.failure_threshold,
+            self.min_calls,
+            self.min_recovery_time,
+            self.consecutive_successes,
+        ))
     }
 }
 
@@ -144,6 +445,11 @@ impl ThroughputAwarePolicy {
         }
     }
 
+    /// Creates a builder for a throughput-aware policy with default settings.
+    pub fn builder() -> ThroughputAwarePolicyBuilder {
+        ThroughputAwarePolicyBuilder::new()
+    }
+
     /// Records a successful call in the EMA window.
     pub fn record_success(&self) {
         self.ema_window.record_success();
@@ -154,8 +460,8 @@ impl ThroughputAwarePolicy {
         self.ema_window.record_failure();
     }
 
-    fn calculate_throughput(&self, stats: &BreakerStats) -> f64 {
-        let total_calls = stats.get_total_calls();
+    fn calculate_throughput(&self, stats: &StatsView) -> f64 {
+        let total_calls = stats.total_calls();
 
         let window_secs = self.throughput_window.as_secs_f64();
         if window_secs <= 0.0 {
@@ -167,16 +473,689 @@ impl ThroughputAwarePolicy {
 }
 
 impl BreakerPolicy for ThroughputAwarePolicy {
-    fn should_trip(&self, stats: &BreakerStats) -> bool {
+    fn should_trip(&self, stats: &StatsView) -> bool {
         let error_rate = self.ema_window.error_rate();
         let throughput = self.calculate_throughput(stats);
 
         error_rate >= self.failure_threshold && throughput >= self.min_throughput_per_second
     }
 
-    fn should_reset(&self, _stats: &BreakerStats) -> bool {
+    fn should_reset(&self, _stats: &StatsView) -> bool {
         // Use EMA error rate for recovery decision
         let error_rate = self.ema_window.error_rate();
         error_rate <= self.recovery_threshold
     }
+
+    fn record_outcome(&self, success: bool, _duration: Duration) {
+        if success {
+            self.record_success();
+        } else {
+            self.record_failure();
+        }
+    }
+
+    fn clear(&self) {
+        self.ema_window.reset();
+    }
+}
+
+/// Builder for [`ThroughputAwarePolicy`].
+///
+/// Defaults to a smoothing factor of 0.1 applied after 10 calls, a failure
+/// threshold of 0.5 at a minimum of 1 call per second measured over 60 seconds,
+/// and a recovery threshold of 0.1.
+#[derive(Debug, Clone)]
+pub struct ThroughputAwarePolicyBuilder {
+    alpha: f64,
+    calls_required: u64,
+    failure_threshold: f64,
+    min_throughput_per_second: f64,
+    throughput_window: Duration,
+    recovery_threshold: f64,
+}
+
+impl Default for ThroughputAwarePolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThroughputAwarePolicyBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            alpha: 0.1,
+            calls_required: 10,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            min_throughput_per_second: 1.0,
+            throughput_window: Duration::from_secs(60),
+            recovery_threshold: 0.1,
+        }
+    }
+
+    /// Sets the smoothing factor of the moving average; higher values react faster.
+    pub fn alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Sets the number of calls before the moving average is considered.
+    pub fn calls_required(mut self, count: u64) -> Self {
+        self.calls_required = count;
+        self
+    }
+
+    /// Sets the moving average error rate that trips the circuit.
+    pub fn failure_threshold(mut self, threshold: f64) -> Self {
+        self.failure_threshold = threshold;
+        self
+    }
+
+    /// Sets the minimum throughput, in calls per second, required to trip.
+    pub fn min_throughput_per_second(mut self, rate: f64) -> Self {
+        self.min_throughput_per_second = rate;
+        self
+    }
+
+    /// Sets the window over which throughput is measured.
+    pub fn throughput_window(mut self, window: Duration) -> Self {
+        self.throughput_window = window;
+        self
+    }
+
+    /// Sets the moving average error rate at or below which the circuit resets.
+    pub fn recovery_threshold(mut self, threshold: f64) -> Self {
+        self.recovery_threshold = threshold;
+        self
+    }
+
+    /// Builds the policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`ThroughputAwarePolicyBuilder::try_build`] to handle invalid values.
+    pub fn build(self) -> ThroughputAwarePolicy {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the policy, or returns every invalid setting.
+    pub fn try_build(self) -> Result<ThroughputAwarePolicy, ConfigError> {
+        let mut errors = ConfigError::new();
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            errors.push(
+                "alpha",
+                format!(
+                    "must be greater than 0.0 and at most 1.0, got {}",
+                    self.alpha
+                ),
+            );
+        }
+        check_ratio(&mut errors, "failure_threshold", self.failure_threshold);
+        if !(self.min_throughput_per_second >= 0.0 && self.min_throughput_per_second.is_finite()) {
+            errors.push(
+                "min_throughput_per_second",
+                format!(
+                    "must be a non-negative number, got {}",
+                    self.min_throughput_per_second
+                ),
+            );
+        }
+        if self.throughput_window.is_zero() {
+            errors.push("throughput_window", "must be greater than zero");
+        }
+        check_ratio(&mut errors, "recovery_threshold", self.recovery_threshold);
+        errors.into_result()?;
+
+        Ok(ThroughputAwarePolicy::new(
+            self.alpha,
+            self.calls_required,
+            self.failure_threshold,
+            self.min_throughput_per_second,
+            self.throughput_window,
+            self.recovery_threshold,
+        ))
+    }
+}
+
+/// Latency policy that trips when a percentile of call latency is too high.
+///
+/// Latencies of both successful and failed calls are kept in a [`LatencyWindow`].
+/// The circuit trips once the window holds enough samples and the chosen
+/// percentile exceeds the threshold, and resets once the window holds enough
+/// samples again and the percentile over it is back at or under the threshold.
+/// For recovery to be judged on the half-open probe calls alone, use a window no
+/// longer than the breaker's cooldown and allow at least `min_samples` probes.
+pub struct LatencyPolicy {
+    window: LatencyWindow,
+    percentile: f64,
+    threshold: Duration,
+    min_samples: u64,
+}
+
+impl LatencyPolicy {
+    /// Creates a new latency policy.
+    ///
+    /// `percentile` is a fraction, e.g. 0.99 for the 99th percentile.
+    pub fn new(
+        window_size: Duration,
+        bucket_count: usize,
+        percentile: f64,
+        threshold: Duration,
+        min_samples: u64,
+    ) -> Self {
+        Self {
+            window: LatencyWindow::new(window_size, bucket_count),
+            percentile,
+            threshold,
+            min_samples,
+        }
+    }
+
+    /// Creates a builder for a latency policy with default settings.
+    pub fn builder() -> LatencyPolicyBuilder {
+        LatencyPolicyBuilder::new()
+    }
+
+    /// Gets the current latency at the policy's percentile, or `None` if no calls
+    /// were recorded in the window.
+    pub fn current_percentile(&self) -> Option<Duration> {
+        self.window.percentile(self.percentile)
+    }
+}
+
+impl BreakerPolicy for LatencyPolicy {
+    fn should_trip(&self, _stats: &StatsView) -> bool {
+        if self.window.count() < self.min_samples {
+            return false;
+        }
+
+        self.current_percentile()
+            .is_some_and(|latency| latency > self.threshold)
+    }
+
+    fn should_reset(&self, _stats: &StatsView) -> bool {
+        if self.window.count() < self.min_samples {
+            return false;
+        }
+
+        self.current_percentile()
+            .is_some_and(|latency| latency <= self.threshold)
+    }
+
+    fn trips_on_success(&self) -> bool {
+        true
+    }
+
+    fn record_outcome(&self, _success: bool, duration: Duration) {
+        self.window.record(duration);
+    }
+
+    fn clear(&self) {
+        self.window.reset();
+    }
+}
+
+/// Builder for [`LatencyPolicy`].
+///
+/// Defaults to tripping when the 99th percentile exceeds one second over at least
+/// 100 calls, in a 10 second window split into 10 buckets.
+#[derive(Debug, Clone)]
+pub struct LatencyPolicyBuilder {
+    window: Duration,
+    buckets: usize,
+    percentile: f64,
+    threshold: Duration,
+    min_samples: u64,
+}
+
+impl Default for LatencyPolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyPolicyBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            window: Duration::from_secs(10),
+            buckets: 10,
+            percentile: 0.99,
+            threshold: Duration::from_secs(1),
+            min_samples: 100,
+        }
+    }
+
+    /// Sets the size of the sliding time window.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the number of buckets the window is divided into.
+    pub fn buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    /// Sets the percentile to watch, as a fraction such as 0.99.
+    pub fn percentile(mut self, percentile: f64) -> Self {
+        self.percentile = percentile;
+        self
+    }
+
+    /// Sets the latency above which the circuit trips.
+    pub fn threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the minimum number of calls in the window before latency is considered.
+    pub fn min_samples(mut self, count: u64) -> Self {
+        self.min_samples = count;
+        self
+    }
+
+    /// Builds the policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`LatencyPolicyBuilder::try_build`] to handle invalid values.
+    pub fn build(self) -> LatencyPolicy {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the policy, or returns every invalid setting.
+    pub fn try_build(self) -> Result<LatencyPolicy, ConfigError> {
+        let mut errors = ConfigError::new();
+        FixedWindow::check(
+            &mut errors,
+            ("window", self.window),
+            ("buckets", self.buckets),
+        );
+        if !(self.percentile > 0.0 && self.percentile <= 1.0) {
+            errors.push(
+                "percentile",
+                format!("must be in (0.0, 1.0], got {}", self.percentile),
+            );
+        }
+        if self.threshold.is_zero() {
+            errors.push("threshold", "must be greater than zero");
+        }
+        errors.into_result()?;
+
+        Ok(LatencyPolicy::new(
+            self.window,
+            self.buckets,
+            self.percentile,
+            self.threshold,
+            self.min_samples,
+        ))
+    }
+}
+
+/// Error budget policy that trips when the budget burns too fast.
+///
+/// Given an SLO target such as 0.999, the error budget is the tolerated error
+/// rate of 0.001, and the burn rate of a window is its error rate divided by the
+/// budget. The circuit trips only when every configured window has at least the
+/// minimum number of calls and burns at or above its threshold, so a short window
+/// confirms that a long window's burn is still ongoing.
+///
+/// The circuit resets after enough consecutive successful probes, or once the
+/// shortest window no longer burns above its threshold.
+pub struct SloBurnRatePolicy {
+    slo_target: f64,
+    windows: Vec<BurnRateWindow>,
+    min_calls: u64,
+    consecutive_successes_threshold: u64,
+}
+
+/// A time window with the burn rate at which it considers the budget at risk.
+struct BurnRateWindow {
+    window: FixedWindow,
+    size: Duration,
+    burn_rate: f64,
+}
+
+impl SloBurnRatePolicy {
+    /// Creates a builder for a burn-rate policy with default settings.
+    pub fn builder() -> SloBurnRatePolicyBuilder {
+        SloBurnRatePolicyBuilder::new()
+    }
+
+    /// Gets the current burn rate of each window, in the order they were configured.
+    pub fn burn_rates(&self) -> Vec<f64> {
+        self.windows
+            .iter()
+            .map(|window| self.burn_rate(window))
+            .collect()
+    }
+
+    fn burn_rate(&self, window: &BurnRateWindow) -> f64 {
+        window.window.error_rate() / (1.0 - self.slo_target)
+    }
+}
+
+impl BreakerPolicy for SloBurnRatePolicy {
+    fn should_trip(&self, _stats: &StatsView) -> bool {
+        self.windows.iter().all(|window| {
+            window.window.call_count() >= self.min_calls
+                && self.burn_rate(window) >= window.burn_rate
+        })
+    }
+
+    fn should_reset(&self, stats: &StatsView) -> bool {
+        if stats.consecutive_successes >= self.consecutive_successes_threshold {
+            return true;
+        }
+
+        self.windows
+            .iter()
+            .min_by_key(|window| window.size)
+            .is_some_and(|window| self.burn_rate(window) < window.burn_rate)
+    }
+
+    fn record_outcome(&self, success: bool, _duration: Duration) {
+        for window in &self.windows {
+            if success {
+                window.window.record_success();
+            } else {
+                window.window.record_failure();
+            }
+        }
+    }
+
+    fn clear(&self) {
+        for window in &self.windows {
+            window.window.reset();
+        }
+    }
+}
+
+/// Builder for [`SloBurnRatePolicy`].
+///
+/// Defaults to an SLO target of 0.999 with windows split into 10 buckets, at
+/// least 10 calls per window, and 3 consecutive successes to reset. Without
+/// explicit windows, the policy uses the common fast-burn pair: a 14.4x burn
+/// rate over both 5 minutes and 1 hour, which spends 2% of a 30 day budget in
+/// an hour.
+#[derive(Debug, Clone)]
+pub struct SloBurnRatePolicyBuilder {
+    slo_target: f64,
+    windows: Vec<(Duration, f64)>,
+    buckets: usize,
+    min_calls: u64,
+    consecutive_successes: u64,
+}
+
+impl Default for SloBurnRatePolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SloBurnRatePolicyBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            slo_target: 0.999,
+            windows: Vec::new(),
+            buckets: 10,
+            min_calls: DEFAULT_MIN_THROUGHPUT,
+            consecutive_successes: DEFAULT_CONSECUTIVE_SUCCESSES,
+        }
+    }
+
+    /// Sets the SLO target, the fraction of calls that should succeed.
+    pub fn slo_target(mut self, target: f64) -> Self {
+        self.slo_target = target;
+        self
+    }
+
+    /// Adds a window that must burn the budget at `burn_rate` or faster for the
+    /// circuit to trip.
+    pub fn window(mut self, size: Duration, burn_rate: f64) -> Self {
+        self.windows.push((size, burn_rate));
+        self
+    }
+
+    /// Sets the number of buckets each window is divided into.
+    pub fn buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    /// Sets the minimum number of calls each window needs before it is considered.
+    pub fn min_calls(mut self, count: u64) -> Self {
+        self.min_calls = count;
+        self
+    }
+
+    /// Sets the number of consecutive successes required to reset the circuit.
+    pub fn consecutive_successes(mut self, count: u64) -> Self {
+        self.consecutive_successes = count;
+        self
+    }
+
+    /// Builds the policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`SloBurnRatePolicyBuilder::try_build`] to handle invalid values.
+    pub fn build(self) -> SloBurnRatePolicy {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the policy, or returns every invalid setting.
+    pub fn try_build(self) -> Result<SloBurnRatePolicy, ConfigError> {
+        let windows = if self.windows.is_empty() {
+            vec![
+                (Duration::from_secs(5 * 60), 14.4),
+                (Duration::from_secs(60 * 60), 14.4),
+            ]
+        } else {
+            self.windows
+        };
+
+        let mut errors = ConfigError::new();
+        if !(self.slo_target > 0.0 && self.slo_target < 1.0) {
+            errors.push(
+                "slo_target",
+                format!(
+                    "must be between 0.0 and 1.0 exclusive, got {}",
+                    self.slo_target
+                ),
+            );
+        }
+        // Bucket counts that fit the shortest window fit the longer ones too
+        let shortest = windows.iter().map(|&(size, _)| size).min();
+        if let Some(shortest) = shortest {
+            FixedWindow::check(&mut errors, ("window", shortest), ("buckets", self.buckets));
+        }
+        for &(_, burn_rate) in &windows {
+            if !(burn_rate > 0.0 && burn_rate.is_finite()) {
+                errors.push(
+                    "burn_rate",
+                    format!("must be a positive number, got {}", burn_rate),
+                );
+            }
+        }
+        if self.consecutive_successes == 0 {
+            errors.push("consecutive_successes", "must be at least 1");
+        }
+        errors.into_result()?;
+
+        Ok(SloBurnRatePolicy {
+            slo_target: self.slo_target,
+            windows: windows
+                .into_iter()
+                .map(|(size, burn_rate)| BurnRateWindow {
+                    window: FixedWindow::new(size, self.buckets),
+                    size,
+                    burn_rate,
+                })
+                .collect(),
+            min_calls: self.min_calls,
+            consecutive_successes_threshold: self.consecutive_successes,
+        })
+    }
+}
+
+/// Client-side adaptive throttling, which sheds load instead of tripping.
+///
+/// Following the client-side throttling described in Google's SRE book, the
+/// policy counts requests and the requests the backend accepted over a sliding
+/// window, and rejects each new call with probability
+/// `max(0, (requests - k * accepts) / (requests + 1))`. While the backend
+/// succeeds, no calls are shed; as it starts to fail, an increasing share of
+/// calls fails fast with [`BreakerError::Throttled`] without being attempted.
+/// Throttled calls count as requests that were not accepted.
+///
+/// The policy never trips the circuit on its own. Combine it with another
+/// policy, e.g. `DefaultPolicy::new(0.5, 10, 5, 3).or(throttle)`, to also open the
+/// circuit.
+///
+/// [`BreakerError::Throttled`]: crate::BreakerError::Throttled
+pub struct AdaptiveThrottlePolicy {
+    window: FixedWindow,
+    k: f64,
+}
+
+impl AdaptiveThrottlePolicy {
+    /// Creates a new adaptive throttling policy.
+    ///
+    /// `k` is the multiplier on accepted requests; lower values shed load sooner.
+    pub fn new(window_size: Duration, bucket_count: usize, k: f64) -> Self {
+        Self {
+            window: FixedWindow::new(window_size, bucket_count),
+            k,
+        }
+    }
+
+    /// Creates a builder for an adaptive throttling policy with default settings.
+    pub fn builder() -> AdaptiveThrottlePolicyBuilder {
+        AdaptiveThrottlePolicyBuilder::new()
+    }
+
+    /// Gets the probability with which the next call will be rejected.
+    pub fn rejection_probability(&self) -> f64 {
+        let requests = self.window.call_count() as f64;
+        let accepts = self.window.success_count() as f64;
+        ((requests - self.k * accepts) / (requests + 1.0)).max(0.0)
+    }
+}
+
+impl BreakerPolicy for AdaptiveThrottlePolicy {
+    fn should_trip(&self, _stats: &StatsView) -> bool {
+        false
+    }
+
+    fn should_reset(&self, _stats: &StatsView) -> bool {
+        true
+    }
+
+    fn admit(&self) -> bool {
+        let probability = self.rejection_probability();
+        if probability > 0.0 && crate::random::next_f64() < probability {
+            // A shed call is a request the backend did not accept
+            self.window.record_failure();
+            return false;
+        }
+        true
+    }
+
+    fn record_outcome(&self, success: bool, _duration: Duration) {
+        if success {
+            self.window.record_success();
+        } else {
+            self.window.record_failure();
+        }
+    }
+
+    fn clear(&self) {
+        self.window.reset();
+    }
+}
+
+/// Builder for [`AdaptiveThrottlePolicy`].
+///
+/// Defaults to `k = 2.0` over a 2 minute window split into 10 buckets, as
+/// suggested in the SRE book.
+#[derive(Debug, Clone)]
+pub struct AdaptiveThrottlePolicyBuilder {
+    window: Duration,
+    buckets: usize,
+    k: f64,
+}
+
+impl Default for AdaptiveThrottlePolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveThrottlePolicyBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            window: Duration::from_secs(120),
+            buckets: 10,
+            k: 2.0,
+        }
+    }
+
+    /// Sets the size of the sliding time window.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the number of buckets the window is divided into.
+    pub fn buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    /// Sets the multiplier on accepted requests, which must be at least 1.0.
+    pub fn k(mut self, k: f64) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Builds the policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`AdaptiveThrottlePolicyBuilder::try_build`] to handle invalid values.
+    pub fn build(self) -> AdaptiveThrottlePolicy {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the policy, or returns every invalid setting.
+    pub fn try_build(self) -> Result<AdaptiveThrottlePolicy, ConfigError> {
+        let mut errors = ConfigError::new();
+        FixedWindow::check(
+            &mut errors,
+            ("window", self.window),
+            ("buckets", self.buckets),
+        );
+        if !(self.k.is_finite() && self.k >= 1.0) {
+            errors.push(
+                "k",
+                format!("must be a number of at least 1.0, got {}", self.k),
+            );
+        }
+        errors.into_result()?;
+
+        Ok(AdaptiveThrottlePolicy::new(
+            self.window,
+            self.buckets,
+            self.k,
+        ))
+    }
 }
@@ -0,0 +1,28 @@
+//! Fast, non-cryptographic random numbers for load shedding and jitter.
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+thread_local! {
+    // Seeded from the standard library's per-process random hash keys
+    static STATE: Cell<u64> = Cell::new(RandomState::new().hash_one(0u64) | 1);
+}
+
+/// Returns a uniformly distributed random number in `[0, 1)`.
+pub(crate) fn next_f64() -> f64 {
+    // The top 53 bits fill the mantissa of an f64
+    (next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Returns a uniformly distributed random `u64`, using xorshift64*.
+pub(crate) fn next_u64() -> u64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    })
+}
@@ -0,0 +1,44 @@
+//! Lock-free rate limiting using the generic cell rate algorithm.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A rate limiter based on the generic cell rate algorithm (GCRA).
+///
+/// GCRA behaves like a token bucket holding `burst` tokens that refills one
+/// token every `interval`, but only stores a single timestamp: the theoretical
+/// arrival time at which the bucket would be full again. A call is allowed if
+/// taking a token does not push that time more than `burst` intervals ahead.
+pub(crate) struct RateLimiter {
+    interval: u64,
+    tolerance: u64,
+    base: Instant,
+    // Theoretical arrival time in nanoseconds after `base`
+    arrival: AtomicU64,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `calls` calls every `period`, of which up to
+    /// `burst` may be made at once.
+    pub(crate) fn new(calls: u32, period: Duration, burst: u32) -> Self {
+        let interval =
+            u64::try_from(period.as_nanos() / u128::from(calls.max(1))).unwrap_or(u64::MAX);
+        Self {
+            interval,
+            tolerance: interval.saturating_mul(u64::from(burst)),
+            base: Instant::now(),
+            arrival: AtomicU64::new(0),
+        }
+    }
+
+    /// Takes a token, or returns `false` if the rate limit is exceeded.
+    pub(crate) fn try_acquire(&self) -> bool {
+        let now = u64::try_from(self.base.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        self.arrival
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |arrival| {
+                let next = arrival.max(now).saturating_add(self.interval);
+                (next - now <= self.tolerance).then_some(next)
+            })
+            .is_ok()
+    }
+}
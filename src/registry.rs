@@ -0,0 +1,118 @@
+//! Named registry of circuit breakers.
+
+use ahash::AHashMap;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+use crate::breaker::{BreakerInner, CircuitBreaker};
+use crate::policy::{BoxedPolicy, BreakerPolicy};
+
+/// A thread-safe collection of circuit breakers keyed by name.
+///
+/// Breakers returned by the registry share their state with the registered
+/// breaker, so a breaker looked up in several places trips and recovers as one.
+/// The error type is chosen at lookup time, allowing the same breaker to guard
+/// calls with different error types.
+pub struct BreakerRegistry<P = BoxedPolicy>
+where
+    P: BreakerPolicy,
+{
+    breakers: RwLock<AHashMap<String, Arc<BreakerInner<P>>>>,
+}
+
+impl<P> Default for BreakerRegistry<P>
+where
+    P: BreakerPolicy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> BreakerRegistry<P>
+where
+    P: BreakerPolicy,
+{
+    /// Creates a new empty registry.
+    pub fn new() -> Self {
+        Self {
+            breakers: RwLock::new(AHashMap::new()),
+        }
+    }
+
+    /// Registers a breaker under the given name, replacing any previous one.
+    pub fn insert<E>(&self, name: impl Into<String>, breaker: CircuitBreaker<P, E>)
+    where
+        E: std::error::Error + 'static,
+    {
+        self.breakers
+            .write()
+            .insert(name.into(), Arc::clone(breaker.inner()));
+    }
+
+    /// Gets the breaker registered under the given name.
+    pub fn get<E>(&self, name: &str) -> Option<CircuitBreaker<P, E>>
+    where
+        E: std::error::Error + 'static,
+    {
+        self.breakers
+            .read()
+            .get(name)
+            .map(|inner| CircuitBreaker::from_inner(Arc::clone(inner)))
+    }
+
+    /// Gets the breaker registered under the given name, creating and registering
+    /// it with `f` if it does not exist yet.
+    pub fn get_or_insert_with<E, F>(&self, name: &str, f: F) -> CircuitBreaker<P, E>
+    where
+        E: std::error::Error + 'static,
+        F: FnOnce() -> CircuitBreaker<P, E>,
+    {
+        if let Some(breaker) = self.get(name) {
+            return breaker;
+        }
+
+        let mut breakers = self.breakers.write();
+        let inner = breakers
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::clone(f().inner()));
+
+        CircuitBreaker::from_inner(Arc::clone(inner))
+    }
+
+    /// Removes the breaker registered under the given name.
+    /// Returns true if a breaker was removed.
+    pub fn remove(&self, name: &str) -> bool {
+        self.breakers.write().remove(name).is_some()
+    }
+
+    /// Returns true if a breaker is registered under the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.breakers.read().contains_key(name)
+    }
+
+    /// Gets the names of all registered breakers, in no particular order.
+    pub fn names(&self) -> Vec<String> {
+        self.breakers.read().keys().cloned().collect()
+    }
+
+    /// Gets the number of registered breakers.
+    pub fn len(&self) -> usize {
+        self.breakers.read().len()
+    }
+
+    /// Returns true if no breakers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.breakers.read().is_empty()
+    }
+}
+
+static GLOBAL: Lazy<BreakerRegistry> = Lazy::new(BreakerRegistry::new);
+
+impl BreakerRegistry {
+    /// Gets the process-wide registry, used by the `#[circuit_breaker]` attribute.
+    pub fn global() -> &'static BreakerRegistry {
+        &GLOBAL
+    }
+}
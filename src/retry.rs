@@ -0,0 +1,443 @@
+//! Retrying calls through a circuit breaker.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::breaker::CircuitBreaker;
+use crate::error::{BreakerError, BreakerResult, ConfigError};
+use crate::policy::BreakerPolicy;
+
+type RetryPredicate<E> = Arc<dyn Fn(&E) -> bool + Send + Sync + 'static>;
+
+/// Retries failed calls through a [`CircuitBreaker`] with exponential backoff.
+///
+/// Every attempt is a separate call through the breaker, so the breaker records
+/// each outcome on its own and may trip between attempts. Retrying stops as soon
+/// as the breaker rejects an attempt, e.g. with [`BreakerError::Open`], when an
+/// error is not retryable, and when the breaker's [`RetryBudget`] is exhausted.
+///
+/// ```rust
+/// use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy, Retry};
+/// use std::time::Duration;
+///
+/// let breaker = CircuitBreaker::<DefaultPolicy, std::io::Error>::builder().build();
+/// let retry = Retry::builder()
+///     .max_attempts(3)
+///     .initial_backoff(Duration::from_millis(10))
+///     .retry_if(|e: &std::io::Error| e.kind() == std::io::ErrorKind::TimedOut)
+///     .build();
+///
+/// let result = retry.call(&breaker, || Ok::<_, std::io::Error>("response"));
+/// assert!(result.is_ok());
+/// ```
+pub struct Retry<E> {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    retry_if: Option<RetryPredicate<E>>,
+}
+
+impl<E> Retry<E> {
+    /// Creates a builder for a retry policy with default settings.
+    pub fn builder() -> RetryBuilder<E> {
+        RetryBuilder::new()
+    }
+
+    /// Gets the maximum number of attempts, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Whether a failed attempt may be retried.
+    pub(crate) fn should_retry(&self, attempt: u32, error: &E) -> bool {
+        attempt < self.max_attempts
+            && self
+                .retry_if
+                .as_ref()
+                .is_none_or(|retry_if| retry_if(error))
+    }
+
+    /// Gets the delay before the attempt following attempt number `attempt`.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        if self.initial_backoff.is_zero() {
+            return Duration::ZERO;
+        }
+        let exponent = i32::try_from(attempt - 1).unwrap_or(i32::MAX);
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+        // Take off a random share of up to `jitter` to spread out retries
+        Duration::from_secs_f64(backoff * (1.0 - self.jitter * crate::random::next_f64()))
+    }
+
+    /// Calls `f` through the breaker, retrying failed attempts.
+    ///
+    /// Returns the result of the last attempt.
+    pub fn call<P, T, F>(&self, breaker: &CircuitBreaker<P, E>, mut f: F) -> BreakerResult<T, E>
+    where
+        P: BreakerPolicy,
+        E: std::error::Error + 'static,
+        F: FnMut() -> Result<T, E>,
+    {
+        let mut attempt = 1;
+        loop {
+            match breaker.call(&mut f) {
+                Err(BreakerError::Operation(e))
+                    if self.should_retry(attempt, &e) && breaker.withdraw_retry() =>
+                {
+                    std::thread::sleep(self.backoff(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<E> Retry<E> {
+    /// Calls the future returned by `f` through the breaker, retrying failed
+    /// attempts after sleeping on the Tokio timer.
+    ///
+    /// Returns the result of the last attempt.
+    pub async fn call_async<P, T, F, Fut>(
+        &self,
+        breaker: &CircuitBreaker<P, E>,
+        mut f: F,
+    ) -> BreakerResult<T, E>
+    where
+        P: BreakerPolicy,
+        E: std::error::Error + 'static,
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match breaker.call_async(&mut f).await {
+                Err(BreakerError::Operation(e))
+                    if self.should_retry(attempt, &e) && breaker.withdraw_retry() =>
+                {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<E> Clone for Retry<E> {
+    fn clone(&self) -> Self {
+        Self {
+            max_attempts: self.max_attempts,
+            initial_backoff: self.initial_backoff,
+            max_backoff: self.max_backoff,
+            multiplier: self.multiplier,
+            jitter: self.jitter,
+            retry_if: self.retry_if.clone(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for Retry<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Retry")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for [`Retry`].
+///
+/// Defaults to 3 attempts with a backoff starting at 100 milliseconds, doubling
+/// after each attempt up to 10 seconds, with full jitter, retrying every error.
+pub struct RetryBuilder<E> {
+    retry: Retry<E>,
+}
+
+impl<E> Default for RetryBuilder<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> RetryBuilder<E> {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            retry: Retry {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(100),
+                max_backoff: Duration::from_secs(10),
+                multiplier: 2.0,
+                jitter: 1.0,
+                retry_if: None,
+            },
+        }
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.retry.max_attempts = attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.retry.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the longest delay between two attempts.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.retry.max_backoff = backoff;
+        self
+    }
+
+    /// Sets the factor the delay grows by after each attempt.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.retry.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the largest share of each delay that is taken off at random, from
+    /// 0.0 for no jitter to 1.0 for full jitter.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.retry.jitter = jitter;
+        self
+    }
+
+    /// Only retries errors for which `predicate` returns `true`.
+    pub fn retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.retry.retry_if = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Builds the retry policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use [`RetryBuilder::try_build`]
+    /// to handle invalid values.
+    pub fn build(self) -> Retry<E> {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the retry policy, or returns every invalid setting.
+    pub fn try_build(self) -> Result<Retry<E>, ConfigError> {
+        let mut errors = ConfigError::new();
+        let retry = &self.retry;
+        if retry.max_attempts == 0 {
+            errors.push("max_attempts", "must be at least 1");
+        }
+        if retry.max_backoff < retry.initial_backoff {
+            errors.push("max_backoff", "must be at least the initial backoff");
+        }
+        if !(retry.multiplier.is_finite() && retry.multiplier >= 1.0) {
+            errors.push(
+                "multiplier",
+                format!("must be a number of at least 1.0, got {}", retry.multiplier),
+            );
+        }
+        if !(0.0..=1.0).contains(&retry.jitter) {
+            errors.push(
+                "jitter",
+                format!("must be between 0.0 and 1.0, got {}", retry.jitter),
+            );
+        }
+        errors.into_result()?;
+
+        Ok(self.retry)
+    }
+}
+
+// Budget amounts are kept in thousandths of a retry
+const MILLIS_PER_RETRY: u64 = 1000;
+
+/// A token bucket limiting retries to a share of successful calls.
+///
+/// Modeled on Finagle's retry budget: every successful call through a breaker the
+/// budget is attached to deposits `ratio` of a retry, and every retry made by
+/// [`Retry`] withdraws a whole one. A small reserve of `min_retries_per_second`
+/// refills over time, so that services with little traffic can still retry. Once
+/// the budget is exhausted, [`Retry`] returns the last error instead of retrying,
+/// which keeps retries from multiplying the load on a failing dependency.
+///
+/// A budget can be shared by several breakers with [`BreakerBuilder::retry_budget`].
+///
+/// [`BreakerBuilder::retry_budget`]: crate::BreakerBuilder::retry_budget
+#[derive(Debug)]
+pub struct RetryBudget {
+    balance: AtomicU64,
+    deposit: u64,
+    max_balance: u64,
+    // Thousandths of a retry added per second
+    refill_rate: u64,
+    base: Instant,
+    // Nanoseconds after `base` up to which the reserve was refilled
+    refilled_until: AtomicU64,
+}
+
+impl RetryBudget {
+    /// Creates a new retry budget, starting with one second's worth of reserve.
+    pub fn new(ratio: f64, min_retries_per_second: u32, max_retries: u32) -> Self {
+        let max_balance = u64::from(max_retries) * MILLIS_PER_RETRY;
+        let refill_rate = u64::from(min_retries_per_second) * MILLIS_PER_RETRY;
+        Self {
+            balance: AtomicU64::new(refill_rate.min(max_balance)),
+            deposit: (ratio * MILLIS_PER_RETRY as f64) as u64,
+            max_balance,
+            refill_rate,
+            base: Instant::now(),
+            refilled_until: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a builder for a retry budget with default settings.
+    pub fn builder() -> RetryBudgetBuilder {
+        RetryBudgetBuilder::new()
+    }
+
+    /// Gets the number of retries the budget currently allows.
+    pub fn balance(&self) -> f64 {
+        self.refill();
+        self.balance.load(Ordering::Relaxed) as f64 / MILLIS_PER_RETRY as f64
+    }
+
+    /// Adds the share of a retry earned by a successful call.
+    pub fn deposit(&self) {
+        self.add(self.deposit);
+    }
+
+    /// Takes one retry from the budget, or returns `false` if it is exhausted.
+    pub fn try_withdraw(&self) -> bool {
+        self.refill();
+        self.balance
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |balance| {
+                balance.checked_sub(MILLIS_PER_RETRY)
+            })
+            .is_ok()
+    }
+
+    /// Adds the reserve accumulated since the last refill.
+    fn refill(&self) {
+        if self.refill_rate == 0 {
+            return;
+        }
+        let now = u64::try_from(self.base.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        let last = self.refilled_until.load(Ordering::Relaxed);
+        let elapsed = u128::from(now.saturating_sub(last));
+        let amount = elapsed * u128::from(self.refill_rate) / 1_000_000_000;
+        if amount == 0 {
+            // Leave the time to accumulate until it is worth something
+            return;
+        }
+        // Only the thread that moves the mark forward adds the reserve for that time
+        if self
+            .refilled_until
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.add(u64::try_from(amount).unwrap_or(u64::MAX));
+        }
+    }
+
+    fn add(&self, amount: u64) {
+        if amount == 0 {
+            return;
+        }
+        let _ = self
+            .balance
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |balance| {
+                Some(balance.saturating_add(amount).min(self.max_balance))
+            });
+    }
+}
+
+/// Builder for [`RetryBudget`].
+///
+/// Defaults to allowing retries for 20% of successful calls, with a reserve of
+/// 10 retries per second, and at most 100 retries saved up.
+#[derive(Debug, Clone)]
+pub struct RetryBudgetBuilder {
+    ratio: f64,
+    min_retries_per_second: u32,
+    max_retries: u32,
+}
+
+impl Default for RetryBudgetBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryBudgetBuilder {
+    /// Creates a new builder with default settings.
+    pub fn new() -> Self {
+        Self {
+            ratio: 0.2,
+            min_retries_per_second: 10,
+            max_retries: 100,
+        }
+    }
+
+    /// Sets the share of a retry each successful call earns, e.g. 0.2 to allow
+    /// one retry for every five successful calls.
+    pub fn ratio(mut self, ratio: f64) -> Self {
+        self.ratio = ratio;
+        self
+    }
+
+    /// Sets the number of retries per second allowed regardless of traffic.
+    pub fn min_retries_per_second(mut self, retries: u32) -> Self {
+        self.min_retries_per_second = retries;
+        self
+    }
+
+    /// Sets the largest number of retries that can be saved up.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Builds the retry budget.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use
+    /// [`RetryBudgetBuilder::try_build`] to handle invalid values.
+    pub fn build(self) -> RetryBudget {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the retry budget, or returns every invalid setting.
+    pub fn try_build(self) -> Result<RetryBudget, ConfigError> {
+        let mut errors = ConfigError::new();
+        if !(self.ratio.is_finite() && self.ratio >= 0.0) {
+            errors.push(
+                "ratio",
+                format!("must be a non-negative number, got {}", self.ratio),
+            );
+        }
+        if self.max_retries == 0 {
+            errors.push("max_retries", "must be at least 1");
+        }
+        errors.into_result()?;
+
+        Ok(RetryBudget::new(
+            self.ratio,
+            self.min_retries_per_second,
+            self.max_retries,
+        ))
+    }
+}
@@ -0,0 +1,269 @@
+//! Tower middleware running a service through a circuit breaker.
+
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use futures::future::BoxFuture;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::breaker::CircuitBreaker;
+use crate::error::BreakerError;
+use crate::policy::BreakerPolicy;
+
+/// Decides which results of a service the breaker records as failures.
+///
+/// Implemented for closures taking the result, e.g. to count HTTP 5xx
+/// responses as failures:
+///
+/// ```rust,ignore
+/// let layer = CircuitBreakerLayer::new(breaker)
+///     .classifier(|result: &Result<Response<Body>, Error>| match result {
+///         Ok(response) => response.status().is_server_error(),
+///         Err(_) => true,
+///     });
+/// ```
+pub trait ResponseClassifier<Res, E> {
+    /// Whether `result` is recorded as a failure.
+    fn is_failure(&self, result: &Result<Res, E>) -> bool;
+}
+
+/// Classifies every error of the service as a failure, and every response as a
+/// success.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ErrorClassifier;
+
+impl<Res, E> ResponseClassifier<Res, E> for ErrorClassifier {
+    fn is_failure(&self, result: &Result<Res, E>) -> bool {
+        result.is_err()
+    }
+}
+
+impl<Res, E, F> ResponseClassifier<Res, E> for F
+where
+    F: Fn(&Result<Res, E>) -> bool,
+{
+    fn is_failure(&self, result: &Result<Res, E>) -> bool {
+        self(result)
+    }
+}
+
+/// A result classified as a failure, carried through the breaker to be handed
+/// back to the caller unchanged.
+struct Classified<Res, E>(Result<Res, E>);
+
+impl<Res, E> Debug for Classified<Res, E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Classified")
+    }
+}
+
+impl<Res, E> Display for Classified<Res, E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Result classified as a failure")
+    }
+}
+
+impl<Res, E> Error for Classified<Res, E> {}
+
+/// A [`Layer`] wrapping services in a [`CircuitBreakerService`].
+///
+/// Every service created by the layer shares the state of the same breaker.
+///
+/// ```rust,ignore
+/// let service = ServiceBuilder::new()
+///     .layer(CircuitBreakerLayer::new(breaker))
+///     .service(client);
+/// ```
+pub struct CircuitBreakerLayer<P, C = ErrorClassifier>
+where
+    P: BreakerPolicy,
+{
+    breaker: CircuitBreaker<P, Infallible>,
+    classifier: C,
+}
+
+impl<P> CircuitBreakerLayer<P>
+where
+    P: BreakerPolicy,
+{
+    /// Creates a layer running services through `breaker`, recording their
+    /// errors as failures.
+    pub fn new<E>(breaker: CircuitBreaker<P, E>) -> Self
+    where
+        E: Error + 'static,
+    {
+        Self {
+            breaker: CircuitBreaker::from_inner(Arc::clone(breaker.inner())),
+            classifier: ErrorClassifier,
+        }
+    }
+}
+
+impl<P, C> CircuitBreakerLayer<P, C>
+where
+    P: BreakerPolicy,
+{
+    /// Sets the classifier deciding which results are recorded as failures.
+    pub fn classifier<C2>(self, classifier: C2) -> CircuitBreakerLayer<P, C2> {
+        CircuitBreakerLayer {
+            breaker: self.breaker,
+            classifier,
+        }
+    }
+}
+
+impl<P, C> Clone for CircuitBreakerLayer<P, C>
+where
+    P: BreakerPolicy,
+    C: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            breaker: self.breaker.clone(),
+            classifier: self.classifier.clone(),
+        }
+    }
+}
+
+impl<S, P, C> Layer<S> for CircuitBreakerLayer<P, C>
+where
+    P: BreakerPolicy,
+    C: Clone,
+{
+    type Service = CircuitBreakerService<S, P, C>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CircuitBreakerService {
+            inner,
+            breaker: self.breaker.clone(),
+            classifier: self.classifier.clone(),
+            cooldown: None,
+        }
+    }
+}
+
+/// A [`Service`] whose calls go through a circuit breaker.
+///
+/// While the circuit is open, [`Service::poll_ready`] reports the service as not
+/// ready until the cooldown has elapsed, so that load balancers and buffers
+/// route around it. Calls that are still rejected, e.g. once the half-open
+/// probes are taken, fail with the matching [`BreakerError`] and the request is
+/// dropped without being sent. Errors of the service itself are returned as
+/// [`BreakerError::Operation`].
+///
+/// The inner service is only called once the breaker admitted the request, by a
+/// clone that takes over the readiness of the original, so it must be `Clone`.
+///
+/// Waiting for the cooldown uses the Tokio timer.
+pub struct CircuitBreakerService<S, P, C = ErrorClassifier>
+where
+    P: BreakerPolicy,
+{
+    inner: S,
+    breaker: CircuitBreaker<P, Infallible>,
+    classifier: C,
+    cooldown: Option<Pin<Box<tokio::time::Sleep>>>,
+}
+
+impl<S, P> CircuitBreakerService<S, P>
+where
+    P: BreakerPolicy,
+{
+    /// Wraps `inner` in `breaker`, recording its errors as failures.
+    pub fn new<E>(inner: S, breaker: CircuitBreaker<P, E>) -> Self
+    where
+        E: Error + 'static,
+    {
+        CircuitBreakerLayer::new(breaker).layer(inner)
+    }
+}
+
+impl<S, P, C> Clone for CircuitBreakerService<S, P, C>
+where
+    S: Clone,
+    P: BreakerPolicy,
+    C: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            breaker: self.breaker.clone(),
+            classifier: self.classifier.clone(),
+            cooldown: None,
+        }
+    }
+}
+
+impl<S, P, C, Req> Service<Req> for CircuitBreakerService<S, P, C>
+where
+    S: Service<Req> + Clone + Send + 'static,
+    S::Response: Send + 'static,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+    P: BreakerPolicy,
+    C: ResponseClassifier<S::Response, S::Error> + Clone + Send + 'static,
+    Req: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BreakerError<S::Error>;
+    type Future = BoxFuture<'static, Result<S::Response, BreakerError<S::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        loop {
+            if let Some(cooldown) = &mut self.cooldown {
+                ready!(cooldown.as_mut().poll(cx));
+                self.cooldown = None;
+            }
+
+            let remaining = self.breaker.remaining_cooldown();
+            if remaining.is_zero() {
+                break;
+            }
+            self.cooldown = Some(Box::pin(tokio::time::sleep(remaining)));
+        }
+
+        self.inner.poll_ready(cx).map_err(BreakerError::Operation)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let breaker = CircuitBreaker::<P, Classified<S::Response, S::Error>>::from_inner(
+            Arc::clone(self.breaker.inner()),
+        );
+        let classifier = self.classifier.clone();
+        // The ready service is moved into the future, and only called once the
+        // breaker admitted the request
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let result = breaker
+                .call_async(|| async move {
+                    let result = inner.call(req).await;
+                    if classifier.is_failure(&result) {
+                        Err(Classified(result))
+                    } else {
+                        Ok(result)
+                    }
+                })
+                .await;
+
+            match result {
+                Ok(result) | Err(BreakerError::Operation(Classified(result))) => {
+                    result.map_err(BreakerError::Operation)
+                }
+                Err(BreakerError::Open) => Err(BreakerError::Open),
+                Err(BreakerError::Throttled) => Err(BreakerError::Throttled),
+                Err(BreakerError::LimitExceeded) => Err(BreakerError::LimitExceeded),
+                Err(BreakerError::BulkheadFull) => Err(BreakerError::BulkheadFull),
+                Err(BreakerError::RateLimited) => Err(BreakerError::RateLimited),
+                Err(BreakerError::Internal(e)) => Err(BreakerError::Internal(e)),
+            }
+        })
+    }
+}
@@ -0,0 +1,496 @@
+//! Declarative breaker configuration loaded from files and the environment.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::breaker::CircuitBreaker;
+use crate::config::{
+    BreakerBuilder, WithPolicy, DEFAULT_CONSECUTIVE_FAILURES, DEFAULT_CONSECUTIVE_SUCCESSES,
+    DEFAULT_COOLDOWN, DEFAULT_FAILURE_THRESHOLD, DEFAULT_MIN_THROUGHPUT, DEFAULT_PROBE_INTERVAL,
+};
+use crate::error::ConfigError;
+use crate::policy::{BoxedPolicy, BreakerPolicy, DefaultPolicy};
+use crate::registry::BreakerRegistry;
+
+const ENV_PREFIX: &str = "CB_";
+
+/// Configuration for a set of named circuit breakers.
+///
+/// The configuration can be deserialized with serde from any supported format
+/// (TOML, YAML, JSON, ...). The settings of a breaker are resolved by layering,
+/// from lowest to highest precedence: the built-in defaults, the `defaults`
+/// section, the breaker's entry in `breakers`, and environment overrides applied
+/// with [`BreakerConfig::apply_env`].
+///
+/// ```toml
+/// [defaults]
+/// cooldown_ms = 30000
+/// failure_threshold = 0.5
+///
+/// [breakers.payments]
+/// cooldown_ms = 5000
+/// policy = { type = "time_based", window_ms = 10000, buckets = 10 }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BreakerConfig {
+    /// Settings shared by all breakers.
+    #[serde(default)]
+    pub defaults: BreakerSettings,
+
+    /// Per-breaker overrides, keyed by breaker name.
+    #[serde(default)]
+    pub breakers: HashMap<String, BreakerSettings>,
+
+    /// Environment overrides, keyed by normalized breaker name.
+    #[serde(skip)]
+    env_overrides: HashMap<String, BreakerSettings>,
+}
+
+/// Settings of a single breaker. Unset fields fall back to the next lower layer.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BreakerSettings {
+    /// Cooldown before the circuit transitions from open to half-open, in milliseconds.
+    pub cooldown_ms: Option<u64>,
+
+    /// Number of probes allowed in half-open state.
+    pub probe_interval: Option<u32>,
+
+    /// Error rate that trips the circuit.
+    pub failure_threshold: Option<f64>,
+
+    /// Minimum number of calls before the error rate is considered.
+    pub min_throughput: Option<u64>,
+
+    /// Number of consecutive failures that trips the circuit.
+    pub consecutive_failures: Option<u64>,
+
+    /// Number of consecutive successes that resets the circuit.
+    pub consecutive_successes: Option<u64>,
+
+    /// Number of recent calls the default policy judges the error rate on.
+    ///
+    /// Only valid with the default policy.
+    pub sliding_window: Option<usize>,
+
+    /// Number of cache lines the call counters are striped across.
+    pub counter_shards: Option<usize>,
+
+    /// Number of calls the bulkhead lets run at the same time.
+    pub max_concurrent_calls: Option<usize>,
+
+    /// Time a call may wait for a free bulkhead slot, in milliseconds.
+    pub max_queue_time_ms: Option<u64>,
+
+    /// Policy used to make trip and reset decisions.
+    pub policy: Option<PolicyConfig>,
+}
+
+/// Selection of the policy used by a breaker, with policy-specific settings.
+///
+/// The failure threshold, minimum throughput and consecutive success count are
+/// taken from the breaker's [`BreakerSettings`]. Unset policy settings use the
+/// defaults of [`TimeBasedPolicyBuilder`], [`ThroughputAwarePolicyBuilder`] and
+/// [`LatencyPolicyBuilder`].
+///
+/// [`TimeBasedPolicyBuilder`]: crate::TimeBasedPolicyBuilder
+/// [`ThroughputAwarePolicyBuilder`]: crate::ThroughputAwarePolicyBuilder
+/// [`LatencyPolicyBuilder`]: crate::LatencyPolicyBuilder
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PolicyConfig {
+    /// [`DefaultPolicy`] based on error rate and consecutive failures.
+    Default,
+
+    /// [`TimeBasedPolicy`](crate::TimeBasedPolicy) based on the error rate in a sliding time window.
+    TimeBased {
+        /// Size of the time window, in milliseconds.
+        #[serde(default)]
+        window_ms: Option<u64>,
+
+        /// Number of buckets the window is divided into.
+        #[serde(default)]
+        buckets: Option<usize>,
+
+        /// Minimum time since the last failure before the circuit may reset, in milliseconds.
+        #[serde(default)]
+        min_recovery_ms: Option<u64>,
+    },
+
+    /// [`ThroughputAwarePolicy`](crate::ThroughputAwarePolicy) based on an exponential moving average of the error rate.
+    ThroughputAware {
+        /// Smoothing factor of the moving average.
+        #[serde(default)]
+        alpha: Option<f64>,
+
+        /// Number of calls before the moving average is considered.
+        #[serde(default)]
+        calls_required: Option<u64>,
+
+        /// Minimum throughput, in calls per second, required to trip.
+        #[serde(default)]
+        min_throughput_per_second: Option<f64>,
+
+        /// Window over which throughput is measured, in milliseconds.
+        #[serde(default)]
+        throughput_window_ms: Option<u64>,
+
+        /// Error rate at or below which the circuit resets.
+        #[serde(default)]
+        recovery_threshold: Option<f64>,
+    },
+
+    /// [`LatencyPolicy`](crate::LatencyPolicy) based on a percentile of call latency.
+    Latency {
+        /// Percentile to watch, as a fraction such as 0.99.
+        #[serde(default)]
+        percentile: Option<f64>,
+
+        /// Latency above which the circuit trips, in milliseconds.
+        #[serde(default)]
+        threshold_ms: Option<u64>,
+
+        /// Minimum number of calls in the window before latency is considered.
+        #[serde(default)]
+        min_samples: Option<u64>,
+
+        /// Size of the time window, in milliseconds.
+        #[serde(default)]
+        window_ms: Option<u64>,
+
+        /// Number of buckets the window is divided into.
+        #[serde(default)]
+        buckets: Option<usize>,
+    },
+}
+
+/// Error returned when an environment override has an invalid value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvError {
+    /// Name of the offending environment variable.
+    pub var: String,
+
+    /// Value that could not be parsed.
+    pub value: String,
+}
+
+impl Display for EnvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid value {:?} for environment variable {}",
+            self.value, self.var
+        )
+    }
+}
+
+impl Error for EnvError {}
+
+impl BreakerSettings {
+    /// Overlays the fields set in `other` on top of these settings.
+    pub fn merge(&mut self, other: &BreakerSettings) {
+        if other.cooldown_ms.is_some() {
+            self.cooldown_ms = other.cooldown_ms;
+        }
+        if other.probe_interval.is_some() {
+            self.probe_interval = other.probe_interval;
+        }
+        if other.failure_threshold.is_some() {
+            self.failure_threshold = other.failure_threshold;
+        }
+        if other.min_throughput.is_some() {
+            self.min_throughput = other.min_throughput;
+        }
+        if other.consecutive_failures.is_some() {
+            self.consecutive_failures = other.consecutive_failures;
+        }
+        if other.consecutive_successes.is_some() {
+            self.consecutive_successes = other.consecutive_successes;
+        }
+        if other.sliding_window.is_some() {
+            self.sliding_window = other.sliding_window;
+        }
+        if other.counter_shards.is_some() {
+            self.counter_shards = other.counter_shards;
+        }
+        if other.max_concurrent_calls.is_some() {
+            self.max_concurrent_calls = other.max_concurrent_calls;
+        }
+        if other.max_queue_time_ms.is_some() {
+            self.max_queue_time_ms = other.max_queue_time_ms;
+        }
+        if other.policy.is_some() {
+            self.policy.clone_from(&other.policy);
+        }
+    }
+
+    /// Creates a builder configured with these settings.
+    ///
+    /// Invalid settings are reported when the breaker is built.
+    pub fn builder<E>(&self) -> BreakerBuilder<BoxedPolicy, E, WithPolicy<BoxedPolicy>>
+    where
+        E: std::error::Error + 'static,
+    {
+        let builder = BreakerBuilder::<DefaultPolicy, E>::new();
+        let builder = self.apply(match self.sliding_window {
+            Some(size) => builder.sliding_window(size),
+            None => builder,
+        });
+
+        match self.policy.as_ref().unwrap_or(&PolicyConfig::Default) {
+            PolicyConfig::Default => builder.boxed(),
+            PolicyConfig::TimeBased {
+                window_ms,
+                buckets,
+                min_recovery_ms,
+            } => builder
+                .time_based(|mut p| {
+                    if let Some(window_ms) = window_ms {
+                        p = p.window(Duration::from_millis(*window_ms));
+                    }
+                    if let Some(buckets) = buckets {
+                        p = p.buckets(*buckets);
+                    }
+                    if let Some(min_recovery_ms) = min_recovery_ms {
+                        p = p.min_recovery_time(Duration::from_millis(*min_recovery_ms));
+                    }
+                    p
+                })
+                .boxed(),
+            PolicyConfig::ThroughputAware {
+                alpha,
+                calls_required,
+                min_throughput_per_second,
+                throughput_window_ms,
+                recovery_threshold,
+            } => builder
+                .throughput_aware(|mut p| {
+                    if let Some(alpha) = alpha {
+                        p = p.alpha(*alpha);
+                    }
+                    if let Some(calls_required) = calls_required {
+                        p = p.calls_required(*calls_required);
+                    }
+                    if let Some(rate) = min_throughput_per_second {
+                        p = p.min_throughput_per_second(*rate);
+                    }
+                    if let Some(window_ms) = throughput_window_ms {
+                        p = p.throughput_window(Duration::from_millis(*window_ms));
+                    }
+                    if let Some(threshold) = recovery_threshold {
+                        p = p.recovery_threshold(*threshold);
+                    }
+                    p
+                })
+                .boxed(),
+            PolicyConfig::Latency {
+                percentile,
+                threshold_ms,
+                min_samples,
+                window_ms,
+                buckets,
+            } => builder
+                .latency(|mut p| {
+                    if let Some(percentile) = percentile {
+                        p = p.percentile(*percentile);
+                    }
+                    if let Some(threshold_ms) = threshold_ms {
+                        p = p.threshold(Duration::from_millis(*threshold_ms));
+                    }
+                    if let Some(min_samples) = min_samples {
+                        p = p.min_samples(*min_samples);
+                    }
+                    if let Some(window_ms) = window_ms {
+                        p = p.window(Duration::from_millis(*window_ms));
+                    }
+                    if let Some(buckets) = buckets {
+                        p = p.buckets(*buckets);
+                    }
+                    p
+                })
+                .boxed(),
+        }
+    }
+
+    /// Applies the breaker-level settings to a builder.
+    fn apply<P, E, S>(&self, builder: BreakerBuilder<P, E, S>) -> BreakerBuilder<P, E, S>
+    where
+        P: BreakerPolicy,
+        E: std::error::Error + 'static,
+    {
+        let builder = match self.counter_shards {
+            Some(shards) => builder.counter_shards(shards),
+            None => builder,
+        };
+        let builder = match self.max_concurrent_calls {
+            Some(calls) => builder.max_concurrent_calls(calls),
+            None => builder,
+        };
+        let builder = match self.max_queue_time_ms {
+            Some(ms) => builder.max_queue_time(Duration::from_millis(ms)),
+            None => builder,
+        };
+
+        builder
+            .failure_threshold(self.failure_threshold.unwrap_or(DEFAULT_FAILURE_THRESHOLD))
+            .min_throughput(self.min_throughput.unwrap_or(DEFAULT_MIN_THROUGHPUT))
+            .cooldown(
+                self.cooldown_ms
+                    .map(Duration::from_millis)
+                    .unwrap_or(DEFAULT_COOLDOWN),
+            )
+            .probe_interval(self.probe_interval.unwrap_or(DEFAULT_PROBE_INTERVAL))
+            .consecutive_failures(
+                self.consecutive_failures
+                    .unwrap_or(DEFAULT_CONSECUTIVE_FAILURES),
+            )
+            .consecutive_successes(
+                self.consecutive_successes
+                    .unwrap_or(DEFAULT_CONSECUTIVE_SUCCESSES),
+            )
+    }
+
+    fn set_from_env(&mut self, field: &str, value: &str) -> Option<()> {
+        match field {
+            "COOLDOWN_MS" => self.cooldown_ms = Some(parse(value)?),
+            "PROBE_INTERVAL" => self.probe_interval = Some(parse(value)?),
+            "FAILURE_THRESHOLD" => self.failure_threshold = Some(parse(value)?),
+            "MIN_THROUGHPUT" => self.min_throughput = Some(parse(value)?),
+            "CONSECUTIVE_FAILURES" => self.consecutive_failures = Some(parse(value)?),
+            "CONSECUTIVE_SUCCESSES" => self.consecutive_successes = Some(parse(value)?),
+            "SLIDING_WINDOW" => self.sliding_window = Some(parse(value)?),
+            "COUNTER_SHARDS" => self.counter_shards = Some(parse(value)?),
+            "MAX_CONCURRENT_CALLS" => self.max_concurrent_calls = Some(parse(value)?),
+            "MAX_QUEUE_TIME_MS" => self.max_queue_time_ms = Some(parse(value)?),
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+const ENV_FIELDS: [&str; 10] = [
+    "COOLDOWN_MS",
+    "PROBE_INTERVAL",
+    "FAILURE_THRESHOLD",
+    "MIN_THROUGHPUT",
+    "CONSECUTIVE_FAILURES",
+    "CONSECUTIVE_SUCCESSES",
+    "SLIDING_WINDOW",
+    "COUNTER_SHARDS",
+    "MAX_CONCURRENT_CALLS",
+    "MAX_QUEUE_TIME_MS",
+];
+
+fn parse<T: FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+/// Normalizes a breaker name for use in an environment variable name.
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl BreakerConfig {
+    /// Applies overrides from the process environment.
+    ///
+    /// Variables are named `CB_<NAME>_<FIELD>`, where `<NAME>` is the breaker name
+    /// upper-cased with every non-alphanumeric character replaced by `_`, and
+    /// `<FIELD>` is one of `COOLDOWN_MS`, `PROBE_INTERVAL`, `FAILURE_THRESHOLD`,
+    /// `MIN_THROUGHPUT`, `CONSECUTIVE_FAILURES`, `CONSECUTIVE_SUCCESSES`,
+    /// `SLIDING_WINDOW`, `COUNTER_SHARDS`, `MAX_CONCURRENT_CALLS` or
+    /// `MAX_QUEUE_TIME_MS`.
+    /// For example, `CB_PAYMENTS_API_COOLDOWN_MS=5000` sets the cooldown of the
+    /// `payments-api` breaker.
+    pub fn apply_env(&mut self) -> Result<(), EnvError> {
+        self.apply_env_vars(
+            std::env::vars_os().filter_map(|(key, value)| {
+                Some((key.into_string().ok()?, value.into_string().ok()?))
+            }),
+        )
+    }
+
+    /// Applies overrides from the given variables, using the same naming scheme
+    /// as [`BreakerConfig::apply_env`].
+    pub fn apply_env_vars<I, K, V>(&mut self, vars: I) -> Result<(), EnvError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (key, value) in vars {
+            let (key, value) = (key.as_ref(), value.as_ref());
+            let Some(rest) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            let Some((name, field)) = ENV_FIELDS.iter().find_map(|field| {
+                let name = rest.strip_suffix(field)?.strip_suffix('_')?;
+                (!name.is_empty()).then_some((name, *field))
+            }) else {
+                continue;
+            };
+
+            self.env_overrides
+                .entry(name.to_string())
+                .or_default()
+                .set_from_env(field, value)
+                .ok_or_else(|| EnvError {
+                    var: key.to_string(),
+                    value: value.to_string(),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the settings of the named breaker from all configuration layers.
+    pub fn settings(&self, name: &str) -> BreakerSettings {
+        let mut settings = self.defaults.clone();
+        if let Some(overrides) = self.breakers.get(name) {
+            settings.merge(overrides);
+        }
+        if let Some(overrides) = self.env_overrides.get(&env_name(name)) {
+            settings.merge(overrides);
+        }
+        settings
+    }
+
+    /// Creates a builder for the named breaker, so that metric sinks and hooks can
+    /// be attached before building.
+    pub fn builder<E>(&self, name: &str) -> BreakerBuilder<BoxedPolicy, E, WithPolicy<BoxedPolicy>>
+    where
+        E: std::error::Error + 'static,
+    {
+        self.settings(name).builder()
+    }
+
+    /// Builds the named breaker.
+    pub fn build<E>(&self, name: &str) -> Result<CircuitBreaker<BoxedPolicy, E>, ConfigError>
+    where
+        E: std::error::Error + 'static,
+    {
+        self.builder(name).try_build_with_policy()
+    }
+
+    /// Builds a registry containing every breaker listed in `breakers`.
+    pub fn registry(&self) -> Result<BreakerRegistry, ConfigError> {
+        let registry = BreakerRegistry::new();
+        for name in self.breakers.keys() {
+            registry.insert(name.clone(), self.build::<std::convert::Infallible>(name)?);
+        }
+        Ok(registry)
+    }
+}
@@ -1,7 +1,8 @@
 //! Circuit breaker state machine implementation.
 
+use crate::time::AtomicInstant;
 use std::sync::atomic::{AtomicU8, Ordering};
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 /// Represents the possible states of a circuit breaker.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,7 +31,7 @@ impl From<u8> for State {
 /// State transitions representation for the circuit breaker.
 pub struct StateManager {
     state: AtomicU8,
-    last_transition: parking_lot::Mutex<Instant>,
+    last_transition: AtomicInstant,
 }
 
 impl StateManager {
@@ -38,7 +39,7 @@ impl StateManager {
     pub fn new() -> Self {
         Self {
             state: AtomicU8::new(State::Closed as u8),
-            last_transition: parking_lot::Mutex::new(Instant::now()),
+            last_transition: AtomicInstant::now(),
         }
     }
 
@@ -48,14 +49,9 @@ impl StateManager {
         State::from(value)
     }
 
-    /// Gets the time of the last state transition.
-    pub fn last_transition_time(&self) -> Instant {
-        *self.last_transition.lock()
-    }
-
     /// Duration since the last state transition.
     pub fn time_in_state(&self) -> Duration {
-        self.last_transition_time().elapsed()
+        self.last_transition.elapsed().unwrap_or_default()
     }
 
     /// Attempts to transition from one state to another.
@@ -67,7 +63,7 @@ impl StateManager {
             .is_ok();
 
         if result {
-            *self.last_transition.lock() = Instant::now();
+            self.last_transition.set_now();
         }
 
         result
@@ -0,0 +1,56 @@
+//! Lock-free timestamps.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// An optional [`Instant`] that can be read and written without locking.
+///
+/// The instant is stored as a nanosecond offset from a base instant taken at
+/// creation, which covers several centuries of uptime.
+#[derive(Debug)]
+pub(crate) struct AtomicInstant {
+    base: Instant,
+    // Offset from `base` plus one, so that zero means no instant
+    offset: AtomicU64,
+}
+
+impl AtomicInstant {
+    /// Creates an empty timestamp.
+    pub(crate) fn none() -> Self {
+        Self {
+            base: Instant::now(),
+            offset: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a timestamp holding the current instant.
+    pub(crate) fn now() -> Self {
+        let instant = Self::none();
+        instant.set_now();
+        instant
+    }
+
+    /// Gets the stored instant, if any.
+    pub(crate) fn get(&self) -> Option<Instant> {
+        match self.offset.load(Ordering::Acquire) {
+            0 => None,
+            offset => Some(self.base + Duration::from_nanos(offset - 1)),
+        }
+    }
+
+    /// Stores the current instant.
+    pub(crate) fn set_now(&self) {
+        let offset = u64::try_from(self.base.elapsed().as_nanos()).unwrap_or(u64::MAX - 1);
+        self.offset.store(offset + 1, Ordering::Release);
+    }
+
+    /// Removes the stored instant.
+    pub(crate) fn clear(&self) {
+        self.offset.store(0, Ordering::Release);
+    }
+
+    /// Time elapsed since the stored instant, or `None` if there is none.
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        self.get().map(|instant| instant.elapsed())
+    }
+}
@@ -1,8 +1,15 @@
-use circuitbreaker_rs::{BreakerError, CircuitBreaker, DefaultPolicy, State};
+use circuitbreaker_rs::{
+    AdaptiveThrottlePolicy, BoxedPolicy, BreakerError, BreakerPolicy, BreakerRegistry,
+    CircuitBreaker, CountWindow, DefaultPolicy, FixedWindow, HookRegistry, LatencyPolicy,
+    LatencyWindow, LimitAlgorithm, MetricSink, Pipeline, PipelineError, PolicyExt, Retry,
+    RetryBudget, SloBurnRatePolicy, State, StatsView, ThroughputAwarePolicy, TimeBasedPolicy,
+};
 use std::error::Error;
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Barrier, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Custom error type that implements Error trait
 #[derive(Debug)]
@@ -99,10 +106,10 @@ fn test_circuit_breaker_basic_functionality() {
     // At this point, we should have either:
     // 1. Successfully transitioned to Closed and result.is_ok() == true, or
     // 2. Hit max attempts and stayed in Open state
-    if result.is_err() {
+    if let Err(err) = result {
         // Only allow Open errors, anything else is unexpected
         assert!(
-            matches!(result.unwrap_err(), BreakerError::Open),
+            matches!(err, BreakerError::Open),
             "Failed call should be due to Open breaker"
         );
     }
@@ -185,6 +192,1069 @@ fn test_circuit_breaker_builder() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_runtime_reconfiguration() {
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let hooks = HookRegistry::new();
+    let recorded = Arc::clone(&changes);
+    hooks.set_on_config_change(move |old, new| {
+        recorded.lock().unwrap().push((*old, *new));
+    });
+
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .consecutive_failures(5)
+        .cooldown(Duration::from_secs(60))
+        .hooks(hooks)
+        .build();
+
+    // Record some failures before changing the configuration
+    for _ in 0..2 {
+        let _ = breaker.call(|| -> Result<(), TestError> { Err(TestError::new("error")) });
+    }
+    assert_eq!(breaker.current_state(), State::Closed);
+
+    breaker
+        .update_config(|config| {
+            config.cooldown = Duration::from_millis(50);
+            config.probe_interval = 1;
+            if let Some(thresholds) = config.thresholds.as_mut() {
+                thresholds.consecutive_failures = 3;
+            }
+        })
+        .unwrap();
+
+    let config = breaker.config();
+    assert_eq!(config.cooldown, Duration::from_millis(50));
+    assert_eq!(config.probe_interval, 1);
+    assert_eq!(config.thresholds.unwrap().consecutive_failures, 3);
+
+    // The failures recorded before the update still count towards the new threshold
+    let _ = breaker.call(|| -> Result<(), TestError> { Err(TestError::new("error")) });
+    assert_eq!(breaker.current_state(), State::Open);
+
+    // The new cooldown applies to the running breaker
+    thread::sleep(Duration::from_millis(100));
+    let result = breaker.call(|| -> Result<(), TestError> { Ok(()) });
+    assert!(result.is_ok());
+
+    let changes = changes.lock().unwrap();
+    assert_eq!(changes.len(), 1);
+    let (old, new) = changes[0];
+    assert_eq!(old.cooldown, Duration::from_secs(60));
+    assert_eq!(old.thresholds.unwrap().consecutive_failures, 5);
+    assert_eq!(new, config);
+}
+
+#[test]
+fn test_builder_validation() {
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .failure_threshold(1.7)
+        .probe_interval(0)
+        .consecutive_successes(0)
+        .try_build()
+        .err()
+        .expect("invalid configuration should be rejected");
+
+    // Every invalid field is reported, not just the first one
+    assert_eq!(err.invalid_fields().len(), 3);
+    assert!(err.is_invalid("failure_threshold"));
+    assert!(err.is_invalid("probe_interval"));
+    assert!(err.is_invalid("consecutive_successes"));
+
+    // A custom policy can only be built after it has been supplied
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .cooldown(Duration::from_secs(1))
+        .policy(DefaultPolicy::new(0.5, 10, 2, 1))
+        .try_build_with_policy()
+        .unwrap();
+    assert_eq!(breaker.config().cooldown, Duration::from_secs(1));
+
+    // Settings of the default policy do not affect a custom one
+    assert!(CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .consecutive_failures(0)
+        .policy(DefaultPolicy::new(0.5, 10, 2, 1))
+        .try_build_with_policy()
+        .is_ok());
+
+    // A boxed default policy reports its own invalid settings
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .failure_threshold(1.7)
+        .sliding_window(0)
+        .boxed()
+        .try_build_with_policy()
+        .err()
+        .expect("invalid boxed policy should be rejected");
+    assert!(err.is_invalid("failure_threshold"));
+    assert!(err.is_invalid("sliding_window"));
+
+    // Invalid runtime updates are rejected without changing the breaker
+    let err = breaker
+        .update_config(|config| {
+            config.probe_interval = 0;
+            config.cooldown = Duration::from_secs(5);
+        })
+        .unwrap_err();
+    assert!(err.is_invalid("probe_interval"));
+    assert_eq!(breaker.config().cooldown, Duration::from_secs(1));
+
+    let err = FixedWindow::try_new(Duration::from_secs(10), 0)
+        .err()
+        .unwrap();
+    assert!(err.is_invalid("bucket_count"));
+}
+
+#[test]
+fn test_policy_builders() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .failure_threshold(0.25)
+        .cooldown(Duration::from_secs(5))
+        .time_based(|p| {
+            p.window(Duration::from_secs(10))
+                .buckets(5)
+                .min_recovery_time(Duration::from_secs(1))
+        })
+        .build_with_policy();
+    assert_eq!(breaker.current_state(), State::Closed);
+
+    let policy = ThroughputAwarePolicy::builder()
+        .alpha(0.3)
+        .throughput_window(Duration::from_secs(30))
+        .try_build();
+    assert!(policy.is_ok());
+
+    let err = TimeBasedPolicy::builder()
+        .buckets(0)
+        .failure_threshold(-0.1)
+        .try_build()
+        .err()
+        .unwrap();
+    assert!(err.is_invalid("buckets"));
+    assert!(err.is_invalid("failure_threshold"));
+
+    // Policy errors are reported together with breaker-level errors
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .probe_interval(0)
+        .throughput_aware(|p| p.alpha(0.0).recovery_threshold(1.5))
+        .try_build_with_policy()
+        .err()
+        .unwrap();
+    assert!(err.is_invalid("probe_interval"));
+    assert!(err.is_invalid("alpha"));
+    assert!(err.is_invalid("recovery_threshold"));
+}
+
+#[test]
+fn test_fixed_window() {
+    let window = Arc::new(FixedWindow::new(Duration::from_millis(100), 4));
+
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let window = Arc::clone(&window);
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    if i % 2 == 0 {
+                        window.record_failure();
+                    } else {
+                        window.record_success();
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert!(window.error_rate() > 0.0);
+
+    // Calls older than the window no longer count
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(window.error_rate(), 0.0);
+
+    window.record_failure();
+    window.record_success();
+    assert_eq!(window.error_rate(), 0.5);
+
+    window.reset();
+    assert_eq!(window.error_rate(), 0.0);
+}
+
+#[test]
+fn test_count_window() {
+    let window = CountWindow::new(4);
+    window.record_failure();
+    window.record_failure();
+    window.record_success();
+    assert_eq!(window.call_count(), 3);
+    assert_eq!(window.failure_count(), 2);
+
+    // Older calls are evicted once the window is full
+    window.record_success();
+    window.record_success();
+    window.record_success();
+    assert_eq!(window.call_count(), 4);
+    assert_eq!(window.failure_count(), 0);
+    assert_eq!(window.error_rate(), 0.0);
+
+    window.reset();
+    assert_eq!(window.call_count(), 0);
+    assert!(CountWindow::try_new(0).is_err());
+}
+
+#[test]
+fn test_sliding_window_breaker() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .sliding_window(10)
+        .failure_threshold(0.5)
+        .min_throughput(10)
+        .consecutive_failures(100)
+        .build();
+
+    // Plenty of old successes do not dilute a burst of recent failures
+    for _ in 0..50 {
+        let _ = breaker.call(|| -> Result<(), TestError> { Ok(()) });
+    }
+    for _ in 0..5 {
+        let _ = breaker.call(|| -> Result<(), TestError> { Err(TestError::new("error")) });
+    }
+    assert_eq!(breaker.current_state(), State::Open);
+
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .sliding_window(0)
+        .try_build()
+        .err()
+        .expect("empty window should be rejected");
+    assert!(err.is_invalid("sliding_window"));
+
+    // A custom policy would silently ignore the window
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .sliding_window(10)
+        .policy(FixedPolicy::new(false, true))
+        .try_build_with_policy()
+        .err()
+        .expect("window with a custom policy should be rejected");
+    assert!(err.is_invalid("sliding_window"));
+
+    // The boxed default policy keeps its window
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .sliding_window(10)
+        .boxed()
+        .build_with_policy();
+    assert_eq!(breaker.current_state(), State::Closed);
+}
+
+#[test]
+fn test_sharded_counters() {
+    let breaker = Arc::new(
+        CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .counter_shards(4)
+            .failure_threshold(0.9)
+            .min_throughput(1000)
+            .consecutive_failures(1000)
+            .build(),
+    );
+
+    // Counts recorded on different threads are added up on read
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let breaker = Arc::clone(&breaker);
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    let _ = breaker.call(|| -> Result<(), TestError> {
+                        if i == 0 {
+                            Err(TestError::new("error"))
+                        } else {
+                            Ok(())
+                        }
+                    });
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(breaker.error_rate(), 0.25);
+    assert_eq!(breaker.current_state(), State::Closed);
+
+    breaker.reset_stats();
+    assert_eq!(breaker.error_rate(), 0.0);
+
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .counter_shards(0)
+        .try_build()
+        .err()
+        .expect("zero shards should be rejected");
+    assert!(err.is_invalid("counter_shards"));
+
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .counter_shards(1024)
+        .try_build()
+        .err()
+        .expect("too many shards should be rejected");
+    assert!(err.is_invalid("counter_shards"));
+}
+
+#[test]
+fn test_stats_snapshot() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .consecutive_failures(10)
+        .build();
+
+    for _ in 0..3 {
+        let _ = breaker.call(|| -> Result<(), TestError> { Ok(()) });
+    }
+    let _ = breaker.call(|| -> Result<(), TestError> { Err(TestError::new("error")) });
+
+    let stats = breaker.stats();
+    assert_eq!(stats.success_count, 3);
+    assert_eq!(stats.failure_count, 1);
+    assert_eq!(stats.total_calls(), 4);
+    assert_eq!(stats.consecutive_failures, 1);
+    assert_eq!(stats.consecutive_successes, 0);
+    assert!(stats.last_failure_time.is_some());
+
+    breaker.reset_stats();
+    assert_eq!(breaker.stats(), StatsView::default());
+
+    // Policies can be evaluated against views built by hand
+    let policy = DefaultPolicy::new(0.5, 10, 5, 3);
+    let view = StatsView {
+        success_count: 4,
+        failure_count: 6,
+        ..StatsView::default()
+    };
+    assert!(policy.should_trip(&view));
+    assert!(!policy.should_trip(&StatsView {
+        success_count: 20,
+        failure_count: 6,
+        ..StatsView::default()
+    }));
+}
+
+// A policy with fixed decisions that counts the outcomes it observes
+struct FixedPolicy {
+    trip: bool,
+    reset: bool,
+    outcomes: Arc<AtomicU64>,
+}
+
+impl FixedPolicy {
+    fn new(trip: bool, reset: bool) -> Self {
+        FixedPolicy {
+            trip,
+            reset,
+            outcomes: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl BreakerPolicy for FixedPolicy {
+    fn should_trip(&self, _stats: &StatsView) -> bool {
+        self.trip
+    }
+
+    fn should_reset(&self, _stats: &StatsView) -> bool {
+        self.reset
+    }
+
+    fn record_outcome(&self, _success: bool, _duration: Duration) {
+        self.outcomes.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_policy_combinators() {
+    let stats = StatsView::default();
+
+    let any = FixedPolicy::new(true, true).or(FixedPolicy::new(false, false));
+    assert!(any.should_trip(&stats));
+    assert!(!any.should_reset(&stats));
+
+    let all = FixedPolicy::new(true, true).and(FixedPolicy::new(false, false));
+    assert!(!all.should_trip(&stats));
+    assert!(all.should_reset(&stats));
+
+    let not = FixedPolicy::new(true, false).not();
+    assert!(!not.should_trip(&stats));
+    assert!(not.should_reset(&stats));
+
+    // Outcomes reach every combined policy, even those that never decide
+    let first = FixedPolicy::new(true, false);
+    let second = FixedPolicy::new(false, false);
+    let (first_outcomes, second_outcomes) = (first.outcomes.clone(), second.outcomes.clone());
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .policy(first.or(second))
+        .build_with_policy();
+
+    let _ = breaker.call(|| -> Result<(), TestError> { Err(TestError::new("error")) });
+    assert_eq!(breaker.current_state(), State::Open);
+    assert_eq!(first_outcomes.load(Ordering::Relaxed), 1);
+    assert_eq!(second_outcomes.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_combinator_reconfiguration() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .policy(DefaultPolicy::new(0.5, 10, 5, 2).or(DefaultPolicy::new(0.9, 100, 2, 1)))
+        .build_with_policy();
+    breaker
+        .update_config(|config| config.cooldown = Duration::from_millis(50))
+        .unwrap();
+    assert_eq!(breaker.config().thresholds.unwrap().consecutive_failures, 5);
+
+    // The second policy keeps its own thresholds and still trips after two failures
+    for _ in 0..2 {
+        let _ = breaker.call(|| -> Result<(), TestError> { Err(TestError::new("error")) });
+    }
+    assert_eq!(breaker.current_state(), State::Open);
+}
+
+#[test]
+fn test_latency_window() {
+    let window = LatencyWindow::new(Duration::from_secs(10), 10);
+    assert_eq!(window.percentile(0.99), None);
+
+    for ms in 1..=100 {
+        window.record(Duration::from_millis(ms));
+    }
+    assert_eq!(window.count(), 100);
+
+    // Percentiles are within the histogram's 12.5% relative error
+    let p50 = window.percentile(0.5).unwrap();
+    assert!(p50 >= Duration::from_millis(50) && p50 <= Duration::from_micros(56_250));
+    let p99 = window.percentile(0.99).unwrap();
+    assert!(p99 >= Duration::from_millis(99) && p99 <= Duration::from_micros(111_375));
+
+    window.reset();
+    assert_eq!(window.count(), 0);
+}
+
+#[test]
+fn test_latency_policy() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .cooldown(Duration::from_millis(250))
+        .latency(|p| {
+            p.window(Duration::from_millis(200))
+                .buckets(2)
+                .percentile(0.5)
+                .threshold(Duration::from_millis(10))
+                .min_samples(3)
+        })
+        .build_with_policy();
+
+    // Slow calls trip the circuit even though they succeed
+    for _ in 0..3 {
+        let _ = breaker.call(|| -> Result<(), TestError> {
+            thread::sleep(Duration::from_millis(20));
+            Ok(())
+        });
+    }
+    assert_eq!(breaker.current_state(), State::Open);
+
+    // Once the slow calls have left the window, a single fast probe is not
+    // enough to judge recovery
+    thread::sleep(Duration::from_millis(300));
+    let result = breaker.call(|| -> Result<(), TestError> { Ok(()) });
+    assert!(result.is_ok());
+    assert_eq!(breaker.current_state(), State::HalfOpen);
+
+    // Enough fast probes close the circuit
+    for _ in 0..2 {
+        assert!(breaker.call(|| -> Result<(), TestError> { Ok(()) }).is_ok());
+    }
+    assert_eq!(breaker.current_state(), State::Closed);
+
+    let err = LatencyPolicy::builder()
+        .percentile(0.0)
+        .threshold(Duration::ZERO)
+        .try_build()
+        .err()
+        .expect("invalid latency policy should be rejected");
+    assert!(err.is_invalid("percentile"));
+    assert!(err.is_invalid("threshold"));
+}
+
+// Feeds a stream of outcomes to a policy, spreading the failures among the successes
+fn feed_outcomes(policy: &impl BreakerPolicy, successes: usize, failures: usize) {
+    let total = successes + failures;
+    for i in 0..total {
+        let failed = i * failures / total != (i + 1) * failures / total;
+        policy.record_outcome(!failed, Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_slo_burn_rate_policy() {
+    // A 10% error budget, burned 5x over 100ms and 2x over one second
+    let policy = SloBurnRatePolicy::builder()
+        .slo_target(0.9)
+        .window(Duration::from_millis(100), 5.0)
+        .window(Duration::from_secs(1), 2.0)
+        .min_calls(10)
+        .build();
+    let stats = StatsView::default();
+
+    // Spending the budget at exactly the allowed rate does not trip
+    feed_outcomes(&policy, 90, 10);
+    assert!(!policy.should_trip(&stats));
+
+    // A short spike burns the short window, but not yet the long one
+    thread::sleep(Duration::from_millis(150));
+    feed_outcomes(&policy, 0, 10);
+    let rates = policy.burn_rates();
+    assert!((rates[0] - 10.0).abs() < 1e-9);
+    assert!(rates[1] < 2.0);
+    assert!(!policy.should_trip(&stats));
+
+    // A sustained burn shows up in both windows
+    feed_outcomes(&policy, 0, 10);
+    assert!(policy.should_trip(&stats));
+
+    // Once the short window recovers the circuit may reset, even though the long
+    // window still shows the burn
+    thread::sleep(Duration::from_millis(150));
+    assert!(!policy.should_trip(&stats));
+    assert!(policy.should_reset(&stats));
+    assert!(policy.burn_rates()[1] >= 2.0);
+
+    let err = SloBurnRatePolicy::builder()
+        .slo_target(1.0)
+        .window(Duration::from_secs(60), -1.0)
+        .try_build()
+        .err()
+        .expect("invalid burn-rate policy should be rejected");
+    assert!(err.is_invalid("slo_target"));
+    assert!(err.is_invalid("burn_rate"));
+}
+
+#[test]
+fn test_adaptive_throttle() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .adaptive_throttle(|p| p.k(2.0).window(Duration::from_secs(60)))
+        .build_with_policy();
+
+    // A healthy backend is never throttled
+    for _ in 0..10 {
+        assert!(breaker.call(|| Ok::<_, TestError>(())).is_ok());
+    }
+
+    // Once the backend fails, a growing share of calls is shed without being attempted
+    let attempted = AtomicU64::new(0);
+    let mut throttled = 0;
+    for _ in 0..1000 {
+        let result = breaker.call(|| {
+            attempted.fetch_add(1, Ordering::Relaxed);
+            Err::<(), _>(TestError::new("unavailable"))
+        });
+        if let Err(BreakerError::Throttled) = result {
+            throttled += 1;
+        }
+    }
+    assert!(throttled > 700, "only {} calls were throttled", throttled);
+    assert_eq!(attempted.load(Ordering::Relaxed), 1000 - throttled);
+
+    // Throttling alone never opens the circuit
+    assert_eq!(breaker.current_state(), State::Closed);
+
+    let policy = AdaptiveThrottlePolicy::builder().build();
+    assert_eq!(policy.rejection_probability(), 0.0);
+    feed_outcomes(&policy, 0, 99);
+    assert!((policy.rejection_probability() - 0.99).abs() < 1e-9);
+    feed_outcomes(&policy, 100, 0);
+    assert_eq!(policy.rejection_probability(), 0.0);
+
+    let err = AdaptiveThrottlePolicy::builder()
+        .k(0.5)
+        .try_build()
+        .err()
+        .expect("invalid throttle policy should be rejected");
+    assert!(err.is_invalid("k"));
+}
+
+#[test]
+fn test_adaptive_limiter() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .adaptive_limit(|l| l.initial_limit(4).min_limit(1).max_limit(8))
+        .consecutive_failures(100)
+        .min_throughput(1000)
+        .build();
+    let limiter = breaker.limiter().expect("breaker should have a limiter");
+
+    // Calls beyond the limit are rejected while the others are in flight
+    fn nest(breaker: &CircuitBreaker<DefaultPolicy, TestError>, depth: usize) -> usize {
+        match breaker.call(|| Ok::<_, TestError>(nest(breaker, depth + 1))) {
+            Ok(depth) => depth,
+            Err(BreakerError::LimitExceeded) => depth,
+            Err(err) => panic!("unexpected error: {}", err),
+        }
+    }
+    assert_eq!(nest(&breaker, 0), 4);
+    assert_eq!(limiter.in_flight(), 0);
+
+    // Failures back the limit off towards its minimum
+    for _ in 0..20 {
+        let _ = breaker.call(|| Err::<(), _>(TestError::new("overloaded")));
+    }
+    assert_eq!(limiter.limit(), 1);
+
+    // Fast successes grow it back, but only while at least half of it is in use
+    for _ in 0..20 {
+        assert!(breaker.call(|| Ok::<_, TestError>(())).is_ok());
+    }
+    assert_eq!(limiter.limit(), 3);
+
+    // The gradient algorithm shrinks the limit once latency rises above the fastest call
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .adaptive_limit(|l| {
+            l.algorithm(LimitAlgorithm::Gradient { smoothing: 1.0 })
+                .initial_limit(100)
+                .max_limit(100)
+        })
+        .build();
+    let limiter = breaker.limiter().expect("breaker should have a limiter");
+    assert!(breaker.call(|| Ok::<_, TestError>(())).is_ok());
+    assert_eq!(limiter.limit(), 100);
+    for _ in 0..10 {
+        let _ = breaker.call(|| {
+            thread::sleep(Duration::from_millis(2));
+            Ok::<_, TestError>(())
+        });
+    }
+    assert!(limiter.limit() < 20, "limit is still {}", limiter.limit());
+
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .adaptive_limit(|l| {
+            l.algorithm(LimitAlgorithm::Aimd {
+                backoff_ratio: 1.5,
+                latency_threshold: Duration::ZERO,
+            })
+            .min_limit(10)
+            .max_limit(5)
+        })
+        .try_build()
+        .err()
+        .expect("invalid limiter should be rejected");
+    assert!(err.is_invalid("backoff_ratio"));
+    assert!(err.is_invalid("latency_threshold"));
+    assert!(err.is_invalid("max_limit"));
+    assert!(err.is_invalid("initial_limit"));
+}
+
+#[test]
+fn test_bulkhead() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .max_concurrent_calls(2)
+        .build();
+    let (started_tx, started_rx) = mpsc::channel();
+    let release = Arc::new(Barrier::new(3));
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let breaker = breaker.clone();
+            let started_tx = started_tx.clone();
+            let release = Arc::clone(&release);
+            thread::spawn(move || {
+                breaker.call(|| {
+                    started_tx.send(()).unwrap();
+                    release.wait();
+                    Ok::<_, TestError>(())
+                })
+            })
+        })
+        .collect();
+    started_rx.recv().unwrap();
+    started_rx.recv().unwrap();
+
+    // A third call is rejected without waiting while both slots are taken
+    assert_eq!(breaker.bulkhead_in_flight(), Some(2));
+    let result = breaker.call(|| Ok::<_, TestError>(()));
+    assert!(matches!(result, Err(BreakerError::BulkheadFull)));
+
+    release.wait();
+    for handle in handles {
+        assert!(handle.join().unwrap().is_ok());
+    }
+    assert_eq!(breaker.bulkhead_in_flight(), Some(0));
+
+    // With a queue time, calls wait for a slot to be released
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .max_concurrent_calls(1)
+        .max_queue_time(Duration::from_secs(5))
+        .build();
+    let holder = {
+        let breaker = breaker.clone();
+        let started_tx = started_tx.clone();
+        thread::spawn(move || {
+            breaker.call(|| {
+                started_tx.send(()).unwrap();
+                thread::sleep(Duration::from_millis(50));
+                Ok::<_, TestError>(())
+            })
+        })
+    };
+    started_rx.recv().unwrap();
+    let start = Instant::now();
+    assert!(breaker.call(|| Ok::<_, TestError>(())).is_ok());
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(holder.join().unwrap().is_ok());
+
+    // Calls to an open circuit fail fast instead of queueing for a slot
+    let holder = {
+        let breaker = breaker.clone();
+        thread::spawn(move || {
+            breaker.call(|| {
+                started_tx.send(()).unwrap();
+                thread::sleep(Duration::from_millis(300));
+                Ok::<_, TestError>(())
+            })
+        })
+    };
+    started_rx.recv().unwrap();
+    breaker.force_open();
+    let start = Instant::now();
+    let result = breaker.call(|| Ok::<_, TestError>(()));
+    assert!(matches!(result, Err(BreakerError::Open)));
+    assert!(start.elapsed() < Duration::from_millis(100));
+    assert_eq!(breaker.bulkhead_in_flight(), Some(1));
+    assert!(holder.join().unwrap().is_ok());
+
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .max_concurrent_calls(0)
+        .try_build()
+        .err()
+        .expect("empty bulkhead should be rejected");
+    assert!(err.is_invalid("max_concurrent_calls"));
+}
+
+#[test]
+fn test_retry() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .consecutive_failures(3)
+        .build();
+    let retry = Retry::builder()
+        .max_attempts(5)
+        .initial_backoff(Duration::from_millis(1))
+        .retry_if(|e: &TestError| e.0 != "fatal")
+        .build();
+
+    // Transient failures are retried until an attempt succeeds
+    let mut attempts = 0;
+    let result = retry.call(&breaker, || {
+        attempts += 1;
+        if attempts < 3 {
+            Err(TestError::new("transient"))
+        } else {
+            Ok(attempts)
+        }
+    });
+    assert!(matches!(result, Ok(3)));
+    assert_eq!(breaker.stats().success_count, 1);
+    assert_eq!(breaker.stats().failure_count, 2);
+
+    // Errors that are not retryable end the retries
+    let mut attempts = 0;
+    let result = retry.call(&breaker, || {
+        attempts += 1;
+        Err::<(), _>(TestError::new("fatal"))
+    });
+    assert!(matches!(result, Err(BreakerError::Operation(_))));
+    assert_eq!(attempts, 1);
+
+    // Retrying stops as soon as the attempts trip the circuit
+    breaker.reset_stats();
+    let mut attempts = 0;
+    let result = retry.call(&breaker, || {
+        attempts += 1;
+        Err::<(), _>(TestError::new("transient"))
+    });
+    assert!(matches!(result, Err(BreakerError::Open)));
+    assert_eq!(attempts, 3);
+
+    let err = Retry::<TestError>::builder()
+        .max_attempts(0)
+        .jitter(2.0)
+        .try_build()
+        .expect_err("invalid retry should be rejected");
+    assert!(err.is_invalid("max_attempts"));
+    assert!(err.is_invalid("jitter"));
+}
+
+// Records the rejections and retry budget levels reported by a breaker
+#[derive(Clone, Default)]
+struct RecordingSink {
+    rejections: Arc<Mutex<Vec<String>>>,
+    levels: Arc<Mutex<Vec<(f64, bool)>>>,
+}
+
+impl MetricSink for RecordingSink {
+    fn record_state_transition(&self, _from: &str, _to: &str) {}
+    fn record_error_rate(&self, _rate: f64) {}
+    fn record_probe_attempt(&self, _success: bool) {}
+    fn record_call(&self, _success: bool, _duration: Duration) {}
+
+    fn record_rejection(&self, reason: &str) {
+        self.rejections.lock().unwrap().push(reason.to_string());
+    }
+
+    fn record_retry_budget(&self, balance: f64, withdrawn: bool) {
+        self.levels.lock().unwrap().push((balance, withdrawn));
+    }
+}
+
+#[test]
+fn test_retry_budget() {
+    let budget = Arc::new(
+        RetryBudget::builder()
+            .ratio(0.5)
+            .min_retries_per_second(0)
+            .max_retries(10)
+            .build(),
+    );
+    let sink = RecordingSink::default();
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .consecutive_failures(100)
+        .min_throughput(1000)
+        .retry_budget(Arc::clone(&budget))
+        .metric_sink(sink.clone())
+        .build();
+    let retry = Retry::builder()
+        .max_attempts(10)
+        .initial_backoff(Duration::ZERO)
+        .build();
+
+    // Without successful calls there is nothing to retry with
+    assert_eq!(budget.balance(), 0.0);
+    let mut attempts = 0;
+    let _ = retry.call(&breaker, || {
+        attempts += 1;
+        Err::<(), _>(TestError::new("transient"))
+    });
+    assert_eq!(attempts, 1);
+
+    // Every successful call earns half a retry, also through other breakers
+    let other = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .retry_budget(Arc::clone(&budget))
+        .build();
+    for _ in 0..4 {
+        assert!(other.call(|| Ok::<_, TestError>(())).is_ok());
+    }
+    assert_eq!(budget.balance(), 2.0);
+
+    let mut attempts = 0;
+    let _ = retry.call(&breaker, || {
+        attempts += 1;
+        Err::<(), _>(TestError::new("transient"))
+    });
+    assert_eq!(attempts, 3);
+    assert_eq!(
+        *sink.levels.lock().unwrap(),
+        vec![(0.0, false), (1.0, true), (0.0, true), (0.0, false)]
+    );
+
+    // The budget saves up no more than its maximum
+    for _ in 0..100 {
+        assert!(other.call(|| Ok::<_, TestError>(())).is_ok());
+    }
+    assert_eq!(budget.balance(), 10.0);
+
+    // A reserve refills over time regardless of traffic
+    let budget = RetryBudget::builder()
+        .ratio(0.0)
+        .min_retries_per_second(100)
+        .build();
+    while budget.try_withdraw() {}
+    thread::sleep(Duration::from_millis(50));
+    assert!(budget.try_withdraw());
+
+    // Concurrent refills never add the same stretch of time twice
+    let budget = Arc::new(
+        RetryBudget::builder()
+            .ratio(0.0)
+            .min_retries_per_second(10)
+            .max_retries(100)
+            .build(),
+    );
+    while budget.try_withdraw() {}
+    let start = Instant::now();
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let budget = Arc::clone(&budget);
+            thread::spawn(move || {
+                while start.elapsed() < Duration::from_millis(200) {
+                    budget.balance();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert!(budget.balance() <= start.elapsed().as_secs_f64() * 10.0);
+
+    let err = RetryBudget::builder()
+        .ratio(-1.0)
+        .max_retries(0)
+        .try_build()
+        .expect_err("invalid retry budget should be rejected");
+    assert!(err.is_invalid("ratio"));
+    assert!(err.is_invalid("max_retries"));
+}
+
+#[test]
+fn test_rate_limit() {
+    let sink = RecordingSink::default();
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .rate_limit(20, Duration::from_secs(1))
+        .rate_limit_burst(3)
+        .metric_sink(sink.clone())
+        .build();
+
+    // A burst is allowed at once, after which calls are rejected without being attempted
+    for _ in 0..3 {
+        assert!(breaker.call(|| Ok::<_, TestError>(())).is_ok());
+    }
+    let result = breaker.call(|| -> Result<(), TestError> { panic!("should not be called") });
+    assert!(matches!(result, Err(BreakerError::RateLimited)));
+
+    // Tokens come back at the configured rate
+    thread::sleep(Duration::from_millis(60));
+    assert!(breaker.call(|| Ok::<_, TestError>(())).is_ok());
+
+    // Rate limiting is reported apart from open circuits
+    breaker.force_open();
+    thread::sleep(Duration::from_millis(60));
+    assert!(matches!(
+        breaker.call(|| Ok::<_, TestError>(())),
+        Err(BreakerError::Open)
+    ));
+    assert_eq!(*sink.rejections.lock().unwrap(), ["rate_limited", "open"]);
+
+    let err = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .rate_limit(0, Duration::from_secs(1))
+        .rate_limit_burst(0)
+        .try_build()
+        .err()
+        .expect("invalid rate limit should be rejected");
+    assert!(err.is_invalid("rate_limit"));
+    assert!(err.is_invalid("rate_limit_burst"));
+}
+
+#[test]
+fn test_pipeline() {
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .consecutive_failures(10)
+        .build();
+    let stats = breaker.clone();
+    let pipeline = Pipeline::builder(breaker)
+        .timeout(Duration::from_millis(20))
+        .retry(
+            Retry::builder()
+                .max_attempts(3)
+                .initial_backoff(Duration::from_millis(1))
+                .build(),
+        )
+        .max_concurrent_calls(4)
+        .build();
+
+    // Slow attempts time out, are recorded as failures and retried
+    let mut attempts = 0;
+    let result = pipeline.call(|| {
+        attempts += 1;
+        if attempts < 3 {
+            thread::sleep(Duration::from_millis(30));
+        }
+        Ok::<_, TestError>(attempts)
+    });
+    assert!(matches!(result, Ok(3)));
+    assert_eq!(stats.stats().failure_count, 2);
+    assert_eq!(stats.stats().success_count, 1);
+
+    // Once the attempts are exhausted, the last error is returned
+    let result = pipeline.call(|| Err::<u32, _>(TestError::new("down")));
+    assert!(matches!(result, Err(PipelineError::Operation(_))));
+
+    // The fallback replaces errors of every layer, including rejections
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .consecutive_failures(1)
+        .build();
+    breaker.force_open();
+    let pipeline = Pipeline::builder(breaker)
+        .fallback(|e| matches!(e, PipelineError::Open).then_some("cached"))
+        .build();
+    assert_eq!(pipeline.call(|| Ok("fresh")).unwrap(), "cached");
+
+    // Rejections by the pipeline's bulkhead are reported like the breaker's own
+    let sink = RecordingSink::default();
+    let rejections = Arc::clone(&sink.rejections);
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+        .metric_sink(sink)
+        .build();
+    let pipeline = Pipeline::builder(breaker).max_concurrent_calls(1).build();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    thread::scope(|scope| {
+        let holder = &pipeline;
+        scope.spawn(move || {
+            holder.call(|| {
+                started_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                Ok::<_, TestError>(())
+            })
+        });
+        started_rx.recv().unwrap();
+        let result = pipeline.call(|| Ok(()));
+        assert!(matches!(result, Err(PipelineError::BulkheadFull)));
+        release_tx.send(()).unwrap();
+    });
+    assert_eq!(*rejections.lock().unwrap(), ["bulkhead_full"]);
+
+    let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder().build();
+    let err = Pipeline::<_, _, ()>::builder(breaker)
+        .timeout(Duration::ZERO)
+        .max_concurrent_calls(0)
+        .try_build()
+        .err()
+        .expect("invalid pipeline should be rejected");
+    assert!(err.is_invalid("timeout"));
+    assert!(err.is_invalid("max_concurrent_calls"));
+}
+
+#[test]
+fn test_breaker_registry() {
+    let registry: BreakerRegistry<DefaultPolicy> = BreakerRegistry::new();
+    assert!(registry.is_empty());
+
+    let created = registry.get_or_insert_with("payments", || {
+        CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .consecutive_failures(1)
+            .build()
+    });
+    let _ = created.call(|| -> Result<(), TestError> { Err(TestError::new("error")) });
+
+    // Existing breakers are returned instead of being recreated
+    let existing = registry.get_or_insert_with("payments", || -> CircuitBreaker<_, TestError> {
+        panic!("breaker should already exist")
+    });
+    assert_eq!(existing.current_state(), State::Open);
+    assert_eq!(registry.len(), 1);
+
+    assert!(registry.remove("payments"));
+    assert!(registry.get::<TestError>("payments").is_none());
+
+    // Boxed policies allow breakers with different policies in one registry
+    let boxed: BreakerRegistry<BoxedPolicy> = BreakerRegistry::default();
+    boxed.insert(
+        "inventory",
+        CircuitBreaker::<BoxedPolicy, TestError>::new(
+            Box::new(DefaultPolicy::new(0.5, 10, 5, 3)),
+            Duration::from_secs(30),
+            5,
+            Arc::new(circuitbreaker_rs::NullMetricSink),
+            Arc::new(HookRegistry::new()),
+        ),
+    );
+    assert!(boxed.contains("inventory"));
+}
+
 #[test]
 fn test_call_timeout() {
     // Modify the test to use a mock approach instead of actual timing
@@ -213,6 +1283,7 @@ fn test_call_timeout() {
 #[cfg(feature = "async")]
 mod async_tests {
     use super::*;
+    use circuitbreaker_rs::Hedge;
 
     #[tokio::test]
     async fn test_async_circuit_breaker() {
@@ -257,4 +1328,881 @@ mod async_tests {
             .await;
         assert!(matches!(result, Err(BreakerError::Open)));
     }
+
+    #[tokio::test]
+    async fn test_async_retry() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .consecutive_failures(10)
+            .build();
+        let retry = Retry::builder()
+            .max_attempts(3)
+            .initial_backoff(Duration::from_millis(1))
+            .build();
+
+        let attempts = AtomicU64::new(0);
+        let result = retry
+            .call_async(&breaker, || async {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                Err::<(), _>(TestError::new("transient"))
+            })
+            .await;
+        assert!(matches!(result, Err(BreakerError::Operation(_))));
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+        assert_eq!(breaker.stats().failure_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_hedged_call() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .cooldown(Duration::from_millis(10))
+            .build();
+        let hedge = Hedge::fixed(Duration::from_millis(20));
+
+        // A slow first attempt is overtaken by the hedge, and then cancelled
+        let attempts = AtomicU64::new(0);
+        let start = Instant::now();
+        let result = breaker
+            .call_async_hedged(&hedge, || async {
+                let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+                if attempt == 0 {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok::<_, TestError>(attempt)
+            })
+            .await;
+        assert!(matches!(result, Ok(1)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(breaker.stats().success_count, 1);
+
+        // A failed hedge falls back to the first attempt, and both are recorded
+        let attempts = AtomicU64::new(0);
+        let result = breaker
+            .call_async_hedged(&hedge, || async {
+                if attempts.fetch_add(1, Ordering::Relaxed) == 0 {
+                    tokio::time::sleep(Duration::from_millis(40)).await;
+                    Ok(())
+                } else {
+                    Err(TestError::new("hedge failed"))
+                }
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(breaker.stats().success_count, 2);
+        assert_eq!(breaker.stats().failure_count, 1);
+
+        // Half-open probes are never hedged
+        breaker.force_open();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let attempts = AtomicU64::new(0);
+        let result = breaker
+            .call_async_hedged(&hedge, || async {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_millis(40)).await;
+                Ok::<_, TestError>(())
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+
+        // A percentile delay starts from the initial delay until enough calls are seen
+        let hedge = Hedge::percentile(0.95, Duration::from_millis(5));
+        assert_eq!(hedge.delay(), Duration::from_millis(5));
+        assert!(Hedge::try_percentile(1.5, Duration::ZERO).is_err());
+
+        // Failed attempts feed the percentile as well
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .min_throughput(100)
+            .consecutive_failures(100)
+            .build();
+        let hedge = Hedge::percentile(0.95, Duration::from_secs(1));
+        for _ in 0..20 {
+            let result = breaker
+                .call_async_hedged(&hedge, || async {
+                    Err::<(), _>(TestError::new("fast failure"))
+                })
+                .await;
+            assert!(result.is_err());
+        }
+        assert!(hedge.delay() < Duration::from_secs(1));
+
+        // Cancelled attempts feed the percentile with the time they ran for, so
+        // the delay does not drift down to the latency of the winners
+        let hedge = Hedge::percentile(0.95, Duration::from_millis(20));
+        for _ in 0..20 {
+            let attempts = AtomicU64::new(0);
+            let result = breaker
+                .call_async_hedged(&hedge, || async {
+                    if attempts.fetch_add(1, Ordering::Relaxed) == 0 {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                    Ok::<_, TestError>(())
+                })
+                .await;
+            assert!(result.is_ok());
+        }
+        assert!(hedge.delay() >= Duration::from_millis(15));
+    }
+
+    #[tokio::test]
+    async fn test_async_bulkhead() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .max_concurrent_calls(1)
+            .max_queue_time(Duration::from_secs(5))
+            .build();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let holder = {
+            let breaker = breaker.clone();
+            tokio::spawn(async move {
+                breaker
+                    .call_async(|| async {
+                        release_rx.await.unwrap();
+                        Ok::<_, TestError>(())
+                    })
+                    .await
+                    .is_ok()
+            })
+        };
+        while breaker.bulkhead_in_flight() != Some(1) {
+            tokio::task::yield_now().await;
+        }
+
+        // The waiting call gets the slot as soon as the holder releases it
+        let waiter = {
+            let breaker = breaker.clone();
+            tokio::spawn(async move {
+                breaker
+                    .call_async(|| async { Ok::<_, TestError>(()) })
+                    .await
+                    .is_ok()
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        release_tx.send(()).unwrap();
+        assert!(holder.await.unwrap());
+        assert!(waiter.await.unwrap());
+        assert_eq!(breaker.bulkhead_in_flight(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_async_pipeline() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .consecutive_failures(10)
+            .build();
+        let pipeline = Pipeline::builder(breaker)
+            .timeout(Duration::from_millis(20))
+            .retry(
+                Retry::builder()
+                    .max_attempts(2)
+                    .initial_backoff(Duration::from_millis(1))
+                    .build(),
+            )
+            .fallback(|e| matches!(e, PipelineError::Timeout).then_some(0))
+            .build();
+
+        // Slow attempts are cancelled and retried
+        let attempts = AtomicU64::new(0);
+        let result = pipeline
+            .call_async(|| async {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok::<_, TestError>(1)
+            })
+            .await;
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        // Once every attempt timed out, the fallback takes over
+        let result = pipeline
+            .call_async(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok::<_, TestError>(1)
+            })
+            .await;
+        assert_eq!(result.unwrap(), 0);
+    }
+}
+
+#[cfg(feature = "tower")]
+mod tower_tests {
+    use super::*;
+    use circuitbreaker_rs::CircuitBreakerLayer;
+    use tower::{service_fn, Layer, Service, ServiceExt};
+
+    #[tokio::test]
+    async fn test_tower_layer() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .consecutive_failures(2)
+            .cooldown(Duration::from_millis(100))
+            .build();
+        let layer = CircuitBreakerLayer::new(breaker.clone());
+        let mut service = layer.layer(service_fn(|fail: bool| async move {
+            if fail {
+                Err(TestError::new("down"))
+            } else {
+                Ok("ok")
+            }
+        }));
+
+        let result = service.ready().await.unwrap().call(false).await;
+        assert_eq!(result.unwrap(), "ok");
+
+        for _ in 0..2 {
+            let result = service.ready().await.unwrap().call(true).await;
+            assert!(matches!(result, Err(BreakerError::Operation(_))));
+        }
+        assert_eq!(breaker.current_state(), State::Open);
+
+        // The service is not ready while the circuit is open
+        let ready = tokio::time::timeout(Duration::from_millis(20), service.ready()).await;
+        assert!(ready.is_err());
+
+        // Once the cooldown elapsed, a probe goes through
+        let result = tokio::time::timeout(Duration::from_secs(1), service.ready())
+            .await
+            .expect("service should become ready after the cooldown")
+            .unwrap()
+            .call(false)
+            .await;
+        assert_eq!(result.unwrap(), "ok");
+        assert_ne!(breaker.current_state(), State::Open);
+    }
+
+    #[tokio::test]
+    async fn test_tower_rejection_not_sent() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder().build();
+        let calls = Arc::new(AtomicU64::new(0));
+        let counted = Arc::clone(&calls);
+        let mut service =
+            CircuitBreakerLayer::new(breaker.clone()).layer(service_fn(move |_: ()| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                async { Ok::<_, TestError>(()) }
+            }));
+
+        // The circuit opens between readiness and the call
+        service.ready().await.unwrap();
+        breaker.force_open();
+        let result = service.call(()).await;
+        assert!(matches!(result, Err(BreakerError::Open)));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tower_classifier() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .consecutive_failures(10)
+            .build();
+        let layer = CircuitBreakerLayer::new(breaker.clone())
+            .classifier(|result: &Result<u16, TestError>| matches!(result, Ok(500..)));
+        let service = layer.layer(service_fn(|status: u16| async move {
+            Ok::<_, TestError>(status)
+        }));
+
+        // Responses classified as failures are still returned to the caller
+        let result = service.clone().oneshot(503).await;
+        assert_eq!(result.unwrap(), 503);
+        let result = service.oneshot(404).await;
+        assert_eq!(result.unwrap(), 404);
+
+        assert_eq!(breaker.stats().failure_count, 1);
+        assert_eq!(breaker.stats().success_count, 1);
+    }
+}
+
+#[cfg(feature = "http")]
+mod http_tests {
+    use super::*;
+    use circuitbreaker_rs::LoadShedLayer;
+    use std::convert::Infallible;
+    use std::task::{Context, Poll};
+    use tower::{Layer, Service, ServiceExt};
+
+    // An HTTP service counting how often it is polled for readiness and called
+    #[derive(Clone, Default)]
+    struct Counting {
+        polls: Arc<AtomicU64>,
+        calls: Arc<AtomicU64>,
+    }
+
+    impl Service<http::Request<()>> for Counting {
+        type Response = http::Response<String>;
+        type Error = Infallible;
+        type Future = std::future::Ready<Result<http::Response<String>, Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: http::Request<()>) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(Ok(http::Response::new("ok".to_string())))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_load_shedding() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .cooldown(Duration::from_secs(30))
+            .build();
+        let inner = Counting::default();
+        let mut service = LoadShedLayer::new(breaker.clone()).layer(inner.clone());
+
+        let response = service
+            .ready()
+            .await
+            .unwrap()
+            .call(http::Request::new(()))
+            .await;
+        assert_eq!(response.unwrap().status(), 200);
+        assert_eq!(inner.polls.load(Ordering::SeqCst), 1);
+
+        // Shed requests reserve no capacity of the inner service and never reach it
+        breaker.force_open();
+        let response = service
+            .ready()
+            .await
+            .unwrap()
+            .call(http::Request::new(()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.body(), "Service Unavailable");
+        assert_eq!(response.headers()["retry-after"], "30");
+        assert_eq!(inner.polls.load(Ordering::SeqCst), 1);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_tests {
+    use super::*;
+    use axum::extract::State as AppState;
+    use axum::routing::get;
+    use axum::Router;
+    use circuitbreaker_rs::{BreakerErrorResponse, LoadShedLayer};
+
+    type Breaker = CircuitBreaker<DefaultPolicy, TestError>;
+
+    async fn failing_call(
+        AppState(breaker): AppState<Breaker>,
+    ) -> Result<&'static str, BreakerErrorResponse> {
+        breaker
+            .call_async(|| async { Err(TestError::new("down")) })
+            .await
+            .map_err(|e| BreakerErrorResponse::new(&breaker, &e))
+    }
+
+    #[tokio::test]
+    async fn test_load_shedding() {
+        let breaker = Breaker::builder()
+            .consecutive_failures(1)
+            .cooldown(Duration::from_secs(30))
+            .build();
+        let app = Router::new()
+            .route("/shed", get(|| async { "ok" }))
+            .route_layer(LoadShedLayer::new(breaker.clone()))
+            .route("/call", get(failing_call))
+            .with_state(breaker.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("http://{addr}/shed"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        // A failed downstream call maps to a bad gateway, and trips the circuit
+        let response = client
+            .get(format!("http://{addr}/call"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 502);
+        assert_eq!(breaker.current_state(), State::Open);
+
+        // Rejected calls and shed requests fail fast with the remaining cooldown
+        for path in ["call", "shed"] {
+            let response = client
+                .get(format!("http://{addr}/{path}"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 503);
+            let retry_after: u64 = response.headers()["retry-after"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((29..=30).contains(&retry_after));
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+mod reqwest_tests {
+    use super::*;
+    use circuitbreaker_rs::{BoxedPolicy, CircuitBreakerMiddleware, RequestRejected};
+    use reqwest_middleware::ClientBuilder;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_reqwest_middleware() {
+        let server = MockServer::start().await;
+        Mock::given(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(path("/fail"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let registry = Arc::new(BreakerRegistry::new());
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(CircuitBreakerMiddleware::new(Arc::clone(&registry), |_| {
+                CircuitBreaker::<BoxedPolicy, TestError>::builder()
+                    .consecutive_failures(2)
+                    .cooldown(Duration::from_secs(30))
+                    .boxed()
+                    .build_with_policy()
+            }))
+            .build();
+        let host = server.address().to_string();
+
+        // Client errors pass through without counting as failures
+        for _ in 0..2 {
+            let response = client.get(format!("{}/missing", server.uri())).send().await;
+            assert_eq!(response.unwrap().status(), 404);
+        }
+        let breaker = registry.get::<TestError>(&host).unwrap();
+        assert_eq!(breaker.stats().failure_count, 0);
+
+        // Server errors are returned and trip the circuit of the host
+        for _ in 0..2 {
+            let response = client.get(format!("{}/fail", server.uri())).send().await;
+            assert_eq!(response.unwrap().status(), 500);
+        }
+        assert_eq!(breaker.current_state(), State::Open);
+
+        let err = client
+            .get(format!("{}/missing", server.uri()))
+            .send()
+            .await
+            .expect_err("request should be rejected");
+        let rejected = RequestRejected::from_error(&err).unwrap();
+        assert!(rejected.is_open());
+        assert_eq!(rejected.host(), host);
+
+        // Connection errors count as failures of their own host
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed = listener.local_addr().unwrap();
+        drop(listener);
+        let err = client
+            .get(format!("http://{closed}/"))
+            .send()
+            .await
+            .expect_err("connection should be refused");
+        assert!(RequestRejected::from_error(&err).is_none());
+        let breaker = registry.get::<TestError>(&closed.to_string()).unwrap();
+        assert_eq!(breaker.stats().failure_count, 1);
+    }
+}
+
+#[cfg(feature = "tonic")]
+mod tonic_tests {
+    use super::*;
+    use circuitbreaker_rs::{BoxedPolicy, GrpcBreakerLayer, GrpcBreakerScope, GrpcBreakerService};
+    use futures::future::BoxFuture;
+    use std::convert::Infallible;
+    use std::task::{Context, Poll};
+    use tonic::body::Body;
+    use tonic::codec::ProstCodec;
+    use tonic::codegen::http;
+    use tonic::server::{Grpc, NamedService, UnaryService};
+    use tonic::transport::{Channel, Server};
+    use tonic::{Code, Status};
+    use tower::{Layer, Service, ServiceExt};
+
+    /// Counts the calls that reached the server.
+    #[derive(Clone, Default)]
+    struct EchoServer {
+        hits: Arc<AtomicU64>,
+    }
+
+    impl NamedService for EchoServer {
+        const NAME: &'static str = "test.Echo";
+    }
+
+    struct EchoOk;
+
+    impl UnaryService<()> for EchoOk {
+        type Response = ();
+        type Future = BoxFuture<'static, Result<tonic::Response<()>, Status>>;
+
+        fn call(&mut self, _request: tonic::Request<()>) -> Self::Future {
+            Box::pin(async { Ok(tonic::Response::new(())) })
+        }
+    }
+
+    impl Service<http::Request<Body>> for EchoServer {
+        type Response = http::Response<Body>;
+        type Error = Infallible;
+        type Future = BoxFuture<'static, Result<http::Response<Body>, Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async move {
+                let response = match req.uri().path() {
+                    "/test.Echo/Ok" => {
+                        let mut grpc = Grpc::new(ProstCodec::<(), ()>::default());
+                        grpc.unary(EchoOk, req).await
+                    }
+                    "/test.Echo/Invalid" => Status::invalid_argument("bad request").into_http(),
+                    _ => Status::unavailable("down").into_http(),
+                };
+                Ok(response)
+            })
+        }
+    }
+
+    async fn unary(
+        client: &mut tonic::client::Grpc<GrpcBreakerService<Channel, BoxedPolicy>>,
+        path: &'static str,
+    ) -> Result<(), Status> {
+        client
+            .ready()
+            .await
+            .map_err(|e| Status::unknown(e.to_string()))?;
+        let path = http::uri::PathAndQuery::from_static(path);
+        client
+            .unary(
+                tonic::Request::new(()),
+                path,
+                ProstCodec::<(), ()>::default(),
+            )
+            .await
+            .map(tonic::Response::into_inner)
+    }
+
+    #[tokio::test]
+    async fn test_grpc_layer() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(EchoServer::default())
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let channel = Channel::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let registry = Arc::new(BreakerRegistry::new());
+        let layer = GrpcBreakerLayer::new(Arc::clone(&registry), |_| {
+            CircuitBreaker::<BoxedPolicy, Status>::builder()
+                .consecutive_failures(2)
+                .cooldown(Duration::from_secs(30))
+                .boxed()
+                .build_with_policy()
+        })
+        .scope(GrpcBreakerScope::Method);
+        let mut client = tonic::client::Grpc::new(layer.layer(channel));
+
+        // Invalid arguments are neither successes nor failures
+        for _ in 0..3 {
+            let status = unary(&mut client, "/test.Echo/Invalid").await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+        let breaker = registry.get::<Status>("test.Echo/Invalid").unwrap();
+        assert_eq!(breaker.stats().success_count, 0);
+        assert_eq!(breaker.stats().failure_count, 0);
+
+        // Unavailable calls trip the circuit of their method only
+        for _ in 0..2 {
+            let status = unary(&mut client, "/test.Echo/Down").await.unwrap_err();
+            assert_eq!(status.code(), Code::Unavailable);
+            assert_eq!(status.message(), "down");
+        }
+        let breaker = registry.get::<Status>("test.Echo/Down").unwrap();
+        assert_eq!(breaker.current_state(), State::Open);
+
+        let status = unary(&mut client, "/test.Echo/Down").await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert!(status.message().starts_with("Circuit breaker is open"));
+
+        unary(&mut client, "/test.Echo/Ok").await.unwrap();
+        let breaker = registry.get::<Status>("test.Echo/Ok").unwrap();
+        assert_eq!(breaker.stats().success_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_ignored_probes() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(EchoServer::default())
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let channel = Channel::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let registry = Arc::new(BreakerRegistry::new());
+        let layer = GrpcBreakerLayer::new(Arc::clone(&registry), |_| {
+            CircuitBreaker::<BoxedPolicy, Status>::builder()
+                .consecutive_failures(1)
+                .cooldown(Duration::from_millis(50))
+                .probe_interval(2)
+                .boxed()
+                .build_with_policy()
+        });
+        let mut client = tonic::client::Grpc::new(layer.layer(channel));
+
+        unary(&mut client, "/test.Echo/Down").await.unwrap_err();
+        let breaker = registry.get::<Status>("test.Echo").unwrap();
+        assert_eq!(breaker.current_state(), State::Open);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // Ignored probes are handed back instead of using up the half-open window
+        for _ in 0..=2 {
+            let status = unary(&mut client, "/test.Echo/Invalid").await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+        assert_eq!(breaker.current_state(), State::HalfOpen);
+
+        unary(&mut client, "/test.Echo/Ok").await.unwrap();
+        assert_eq!(breaker.stats().success_count, 1);
+    }
+
+    /// Counts the requests handed to the channel.
+    #[derive(Clone)]
+    struct CountingChannel {
+        inner: Channel,
+        calls: Arc<AtomicU64>,
+    }
+
+    impl Service<http::Request<Body>> for CountingChannel {
+        type Response = http::Response<Body>;
+        type Error = tonic::transport::Error;
+        type Future = <Channel as Service<http::Request<Body>>>::Future;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.inner.call(req)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_rejection_not_sent() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = EchoServer::default();
+        let hits = Arc::clone(&server.hits);
+        tokio::spawn(
+            Server::builder()
+                .add_service(server)
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let channel = Channel::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let calls = Arc::new(AtomicU64::new(0));
+        let registry = Arc::new(BreakerRegistry::new());
+        let mut service = GrpcBreakerLayer::new(Arc::clone(&registry), |_| {
+            CircuitBreaker::<BoxedPolicy, Status>::builder()
+                .cooldown(Duration::from_secs(30))
+                .boxed()
+                .build_with_policy()
+        })
+        .layer(CountingChannel {
+            inner: channel,
+            calls: Arc::clone(&calls),
+        });
+        let request = || {
+            http::Request::builder()
+                .uri(format!("http://{addr}/test.Echo/Down"))
+                .header("content-type", "application/grpc")
+                .header("te", "trailers")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        service
+            .ready()
+            .await
+            .unwrap()
+            .call(request())
+            .await
+            .unwrap();
+        assert_eq!(hits.load(Ordering::Relaxed), 1);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        // Calls rejected by an open circuit are never handed to the channel
+        registry.get::<Status>("test.Echo").unwrap().force_open();
+        for _ in 0..3 {
+            let err = service
+                .ready()
+                .await
+                .unwrap()
+                .call(request())
+                .await
+                .unwrap_err();
+            let status = err.downcast::<Status>().unwrap();
+            assert_eq!(status.code(), Code::Unavailable);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(hits.load(Ordering::Relaxed), 1);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
+
+#[cfg(feature = "macros")]
+mod macro_tests {
+    use super::*;
+    use circuitbreaker_rs::circuit_breaker;
+
+    #[circuit_breaker(name = "macro-sync", fallback = "cached_price")]
+    fn price(item: &str, mut quantity: u32) -> Result<u32, TestError> {
+        quantity *= 10;
+        if item == "broken" {
+            return Err(TestError::new("pricing failed"));
+        }
+        Ok(quantity)
+    }
+
+    fn cached_price(_item: &str, quantity: u32) -> Result<u32, TestError> {
+        Ok(quantity)
+    }
+
+    struct Inventory {
+        stock: u32,
+    }
+
+    impl Inventory {
+        #[circuit_breaker(name = "macro-async", fallback = "cached_stock")]
+        async fn stock(&self, fail: bool) -> Result<u32, TestError> {
+            tokio::task::yield_now().await;
+            if fail {
+                Err(TestError::new("inventory down"))?;
+            }
+            Ok(self.stock)
+        }
+
+        async fn cached_stock(&self, _fail: bool) -> Result<u32, TestError> {
+            Ok(0)
+        }
+    }
+
+    #[circuit_breaker]
+    fn unnamed() -> Result<(), TestError> {
+        Ok(())
+    }
+
+    mod orders {
+        use super::*;
+
+        #[circuit_breaker]
+        pub fn lookup(fail: bool) -> Result<(), TestError> {
+            if fail {
+                return Err(TestError::new("orders down"));
+            }
+            Ok(())
+        }
+    }
+
+    mod users {
+        use super::*;
+
+        #[circuit_breaker]
+        pub fn lookup(fail: bool) -> Result<(), TestError> {
+            if fail {
+                return Err(TestError::new("users down"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_circuit_breaker_macro() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .consecutive_failures(1)
+            .cooldown(Duration::from_secs(30))
+            .boxed()
+            .build_with_policy();
+        BreakerRegistry::global().insert("macro-sync", breaker.clone());
+
+        assert_eq!(price("apple", 2).unwrap(), 20);
+        assert!(matches!(
+            price("broken", 2),
+            Err(BreakerError::Operation(_))
+        ));
+
+        // The fallback gets the arguments once the circuit is open
+        assert_eq!(breaker.current_state(), State::Open);
+        assert_eq!(price("apple", 2).unwrap(), 2);
+
+        // Breakers that were not registered are created with the defaults
+        unnamed().unwrap();
+        assert!(BreakerRegistry::global().contains("integration::macro_tests::unnamed"));
+
+        // Functions of the same name in different modules get their own breakers
+        for _ in 0..5 {
+            assert!(orders::lookup(true).is_err());
+        }
+        assert!(matches!(orders::lookup(false), Err(BreakerError::Open)));
+        assert!(users::lookup(false).is_ok());
+        let registry = BreakerRegistry::global();
+        let orders = registry
+            .get::<TestError>("integration::macro_tests::orders::lookup")
+            .unwrap();
+        let users = registry
+            .get::<TestError>("integration::macro_tests::users::lookup")
+            .unwrap();
+        assert_eq!(orders.current_state(), State::Open);
+        assert_eq!(users.current_state(), State::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_macro_async() {
+        let breaker = CircuitBreaker::<DefaultPolicy, TestError>::builder()
+            .consecutive_failures(1)
+            .cooldown(Duration::from_secs(30))
+            .boxed()
+            .build_with_policy();
+        BreakerRegistry::global().insert("macro-async", breaker.clone());
+        let inventory = Inventory { stock: 5 };
+
+        assert_eq!(inventory.stock(false).await.unwrap(), 5);
+        assert!(matches!(
+            inventory.stock(true).await,
+            Err(BreakerError::Operation(_))
+        ));
+        assert_eq!(breaker.current_state(), State::Open);
+        assert_eq!(inventory.stock(false).await.unwrap(), 0);
+    }
 }
@@ -0,0 +1,173 @@
+#![cfg(feature = "serde")]
+
+use circuitbreaker_rs::{BreakerConfig, BreakerError, PolicyConfig, State};
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct TestError(String);
+
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Test error: {}", self.0)
+    }
+}
+
+impl Error for TestError {}
+
+const CONFIG: &str = r#"
+[defaults]
+cooldown_ms = 10000
+consecutive_failures = 4
+
+[breakers.payments]
+cooldown_ms = 500
+consecutive_failures = 2
+
+[breakers.search]
+failure_threshold = 0.25
+policy = { type = "time_based", window_ms = 5000, buckets = 5 }
+
+[breakers.reports]
+policy = { type = "latency", percentile = 0.95, threshold_ms = 2000 }
+"#;
+
+#[test]
+fn test_config_from_toml() {
+    let config: BreakerConfig = toml::from_str(CONFIG).unwrap();
+
+    let payments = config.settings("payments");
+    assert_eq!(payments.cooldown_ms, Some(500));
+    assert_eq!(payments.consecutive_failures, Some(2));
+
+    let search = config.settings("search");
+    assert_eq!(search.cooldown_ms, Some(10000));
+    assert_eq!(search.failure_threshold, Some(0.25));
+    assert_eq!(
+        search.policy,
+        Some(PolicyConfig::TimeBased {
+            window_ms: Some(5000),
+            buckets: Some(5),
+            min_recovery_ms: None,
+        })
+    );
+
+    assert_eq!(
+        config.settings("reports").policy,
+        Some(PolicyConfig::Latency {
+            percentile: Some(0.95),
+            threshold_ms: Some(2000),
+            min_samples: None,
+            window_ms: None,
+            buckets: None,
+        })
+    );
+    assert!(config.build::<TestError>("reports").is_ok());
+
+    let breaker = config.build::<TestError>("payments").unwrap();
+    assert_eq!(breaker.config().cooldown, Duration::from_millis(500));
+
+    for _ in 0..2 {
+        let _ = breaker.call(|| -> Result<(), TestError> { Err(TestError("error".into())) });
+    }
+    assert_eq!(breaker.current_state(), State::Open);
+}
+
+#[test]
+fn test_config_from_json() {
+    let config: BreakerConfig = serde_json::from_str(
+        r#"{
+            "defaults": { "probe_interval": 2 },
+            "breakers": {
+                "inventory": {
+                    "policy": { "type": "throughput_aware", "alpha": 0.5 }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let breaker = config.build::<TestError>("inventory").unwrap();
+    assert_eq!(breaker.config().probe_interval, 2);
+    // Only the default policy exposes runtime-tunable thresholds
+    assert_eq!(breaker.config().thresholds, None);
+}
+
+#[test]
+fn test_config_rejects_unknown_fields() {
+    let result = toml::from_str::<BreakerConfig>("[defaults]\ncooldown = 5\n");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_config_validation() {
+    let config: BreakerConfig = toml::from_str(
+        r#"
+        [breakers.broken]
+        failure_threshold = 2.0
+        policy = { type = "time_based", buckets = 0 }
+        "#,
+    )
+    .unwrap();
+
+    let err = config.build::<TestError>("broken").err().unwrap();
+    assert!(err.is_invalid("failure_threshold"));
+    assert!(err.is_invalid("buckets"));
+    assert!(config.registry().is_err());
+}
+
+#[test]
+fn test_env_overrides() {
+    let mut config: BreakerConfig = toml::from_str(CONFIG).unwrap();
+    config
+        .apply_env_vars([
+            ("CB_PAYMENTS_COOLDOWN_MS", "750"),
+            ("CB_USER_API_PROBE_INTERVAL", "7"),
+            ("CB_PAYMENTS_SLIDING_WINDOW", "20"),
+            ("CB_SEARCH_MAX_CONCURRENT_CALLS", "8"),
+            ("UNRELATED", "value"),
+        ])
+        .unwrap();
+
+    assert_eq!(config.settings("payments").cooldown_ms, Some(750));
+    assert_eq!(config.settings("user-api").probe_interval, Some(7));
+    assert_eq!(config.settings("search").cooldown_ms, Some(10000));
+    assert_eq!(config.settings("payments").sliding_window, Some(20));
+    assert_eq!(config.settings("search").max_concurrent_calls, Some(8));
+    assert!(config.build::<TestError>("payments").is_ok());
+    let search = config.build::<TestError>("search").unwrap();
+    assert_eq!(search.bulkhead_in_flight(), Some(0));
+
+    // The window only applies to the default policy
+    config
+        .apply_env_vars([("CB_SEARCH_SLIDING_WINDOW", "20")])
+        .unwrap();
+    let err = config.build::<TestError>("search").err().unwrap();
+    assert!(err.is_invalid("sliding_window"));
+
+    let err = config
+        .apply_env_vars([("CB_PAYMENTS_FAILURE_THRESHOLD", "high")])
+        .unwrap_err();
+    assert_eq!(err.var, "CB_PAYMENTS_FAILURE_THRESHOLD");
+}
+
+#[test]
+fn test_config_registry() {
+    let config: BreakerConfig = toml::from_str(CONFIG).unwrap();
+    let registry = config.registry().unwrap();
+
+    let mut names = registry.names();
+    names.sort();
+    assert_eq!(names, ["payments", "reports", "search"]);
+
+    // Lookups share state with each other
+    let first = registry.get::<TestError>("payments").unwrap();
+    let second = registry.get::<std::io::Error>("payments").unwrap();
+    assert!(first.force_open());
+    assert_eq!(second.current_state(), State::Open);
+    assert!(matches!(
+        second.call(|| Ok::<_, std::io::Error>(())),
+        Err(BreakerError::Open)
+    ));
+}